@@ -0,0 +1,107 @@
+//! Finding many paths in parallel with [rayon].
+
+use std::sync::Mutex;
+
+use glam::IVec2;
+use rayon::prelude::*;
+
+use crate::{pathfinder::Pathfinder, pathmap::PathMap};
+
+/// Find paths for many `(start, goal)` queries in parallel using [Pathfinder::astar].
+///
+/// A [Pathfinder] is created for each thread in the current rayon thread pool
+/// and re-used for every query that thread handles, so internal data
+/// structures aren't allocated for every query.
+///
+/// Returns a path for each query in the same order as the queries, or [None]
+/// for queries where no path could be found.
+///
+/// # Example
+/// ```
+/// use glam::IVec2;
+/// use sark_pathfinding::{batch::batch_astar, *};
+/// let map = PathMap2d::new([10, 10]);
+/// let queries: Vec<_> = (0..10).map(|y| (IVec2::new(0, y), IVec2::new(9, y))).collect();
+/// let paths = batch_astar(&map, &queries);
+/// assert!(paths.iter().all(|p| p.as_ref().unwrap().len() == 10));
+/// ```
+pub fn batch_astar<M>(map: &M, queries: &[(IVec2, IVec2)]) -> Vec<Option<Vec<IVec2>>>
+where
+    M: PathMap + Sync,
+    M::Cost: Send,
+{
+    // Only the worker with a given index ever locks its pathfinder, so the
+    // locks are never contended.
+    let pathfinders: Vec<_> = (0..rayon::current_num_threads())
+        .map(|_| Mutex::new(Pathfinder::<M::Cost>::new()))
+        .collect();
+    queries
+        .par_iter()
+        .map(|(start, goal)| {
+            let i = rayon::current_thread_index().unwrap_or(0);
+            let mut pf = pathfinders[i % pathfinders.len()].lock().unwrap();
+            pf.astar(map, *start, *goal).map(|p| p.to_vec())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pathmap::Adjacency, PathMap2d};
+
+    #[test]
+    fn matches_sequential() {
+        let mut map = PathMap2d::new([30, 30]);
+        for y in 0..29 {
+            map.add_obstacle([15, y]);
+        }
+        map.add_obstacle([5, 5]);
+        let queries: Vec<_> = (0..200)
+            .map(|i| (IVec2::new(i % 15, i / 15), IVec2::new(29 - i % 10, i % 30)))
+            .chain([(IVec2::ZERO, IVec2::new(5, 5))])
+            .collect();
+        let paths = batch_astar(&map, &queries);
+        assert_eq!(queries.len(), paths.len());
+
+        let mut pf = Pathfinder::new();
+        for ((start, goal), path) in queries.iter().zip(paths) {
+            let expected = pf.astar(&map, *start, *goal).map(|p| p.to_vec());
+            assert_eq!(expected, path);
+        }
+
+        let mut map = PathMap2d::with_adjacency(
+            [30, 30],
+            Adjacency::Octile {
+                cardinal_cost: 1.0,
+                diagonal_cost: std::f32::consts::SQRT_2,
+            },
+        );
+        for y in 0..29 {
+            map.add_obstacle([15, y]);
+        }
+        let paths = batch_astar(&map, &queries);
+        let mut pf = Pathfinder::new();
+        for ((start, goal), path) in queries.iter().zip(paths) {
+            let expected = pf.astar(&map, *start, *goal).map(|p| p.to_vec());
+            assert_eq!(expected, path);
+        }
+    }
+
+    #[test]
+    fn custom_pool() {
+        let map = PathMap2d::new([20, 20]);
+        let queries: Vec<_> = (0..50)
+            .map(|i| (IVec2::new(0, i % 20), IVec2::new(19, 19 - i % 20)))
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(3)
+            .build()
+            .unwrap();
+        let paths = pool.install(|| batch_astar(&map, &queries));
+        let mut pf = Pathfinder::new();
+        for ((start, goal), path) in queries.iter().zip(paths) {
+            assert_eq!(pf.astar(&map, *start, *goal).map(|p| p.to_vec()), path);
+        }
+    }
+}
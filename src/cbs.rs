@@ -0,0 +1,388 @@
+//! An optimal solver for planning collision free paths for a small group of agents
+//! using [Conflict-Based Search].
+//!
+//! [Conflict-Based Search]: https://www.aaai.org/ocs/index.php/AAAI/AAAI12/paper/viewFile/5062/5239
+
+use ahash::{HashSet, HashSetExt};
+use glam::IVec2;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::{cost::Cost, pathmap::PathMap, state_search::StateSearch};
+
+const DEFAULT_MAX_TIME: u32 = 256;
+const DEFAULT_MAX_NODES: usize = 10_000;
+
+/// A restriction placed on a single agent during a [Cbs] search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Constraint {
+    /// The agent may not be at `pos` at timestep `t`.
+    Vertex { agent: usize, pos: IVec2, t: u32 },
+    /// The agent may not move from `from` to `to` leaving at timestep `t`.
+    Edge {
+        agent: usize,
+        from: IVec2,
+        to: IVec2,
+        t: u32,
+    },
+}
+
+impl Constraint {
+    fn agent(&self) -> usize {
+        match self {
+            Constraint::Vertex { agent, .. } => *agent,
+            Constraint::Edge { agent, .. } => *agent,
+        }
+    }
+}
+
+/// A node in the constraint tree.
+struct CtNode<C> {
+    constraints: Vec<Constraint>,
+    paths: Vec<Vec<IVec2>>,
+    costs: Vec<C>,
+}
+
+impl<C: Cost> CtNode<C> {
+    fn total_cost(&self) -> C {
+        self.costs.iter().fold(C::ZERO, |sum, c| sum + *c)
+    }
+}
+
+/// An entry in the open list of constraint tree nodes.
+struct OpenNode<C> {
+    cost: C,
+    index: usize,
+}
+
+impl<C: Cost> PartialEq for OpenNode<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C: Cost> Eq for OpenNode<C> {}
+
+impl<C: Cost> Ord for OpenNode<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // order by cost, then by the order nodes were created
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl<C: Cost> PartialOrd for OpenNode<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A solver for planning collision free paths for multiple agents using
+/// [Conflict-Based Search].
+///
+/// Each step along a path takes one timestep and an agent can choose to wait in
+/// place, which costs the same as `map.cost(p, p)`. Agents may never occupy
+/// the same position at the same time or swap positions along the same edge.
+/// Once an agent reaches its goal it remains there.
+///
+/// The resulting paths are optimal in terms of the sum of the costs of every
+/// agent's path, but the search time grows quickly with the number of agents
+/// and how much they get in each other's way. It is best suited to small groups
+/// of agents.
+///
+/// [Conflict-Based Search]: https://www.aaai.org/ocs/index.php/AAAI/AAAI12/paper/viewFile/5062/5239
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::*;
+/// use glam::IVec2;
+/// let map = PathMap2d::new([5, 5]);
+/// let mut cbs = Cbs::new();
+/// let agents = [
+///     (IVec2::new(0, 2), IVec2::new(4, 2)),
+///     (IVec2::new(4, 2), IVec2::new(0, 2)),
+/// ];
+/// let paths = cbs.solve(&map, &agents).unwrap();
+/// assert_eq!(2, paths.len());
+/// ```
+pub struct Cbs<C = i32> {
+    search: StateSearch<(IVec2, u32), C>,
+    max_time: u32,
+    max_nodes: usize,
+}
+
+impl<C: Cost> Default for Cbs<C> {
+    fn default() -> Self {
+        Self {
+            search: StateSearch::new(),
+            max_time: DEFAULT_MAX_TIME,
+            max_nodes: DEFAULT_MAX_NODES,
+        }
+    }
+}
+
+impl<C: Cost> Cbs<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of timesteps any single agent's path may take.
+    pub fn with_max_time(mut self, max_time: u32) -> Self {
+        self.max_time = max_time;
+        self
+    }
+
+    /// Set the maximum number of nodes in the constraint tree to expand before
+    /// giving up.
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Find a collision free path for every agent, where each agent is
+    /// defined by a start and goal position.
+    ///
+    /// Returns one path per agent in the same order as `agents`, where each point
+    /// in a path is the agent's position at the next timestep. Returns [None] if
+    /// no solution could be found within the configured limits.
+    pub fn solve(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        agents: &[(IVec2, IVec2)],
+    ) -> Option<Vec<Vec<IVec2>>> {
+        let mut root = CtNode {
+            constraints: Vec::new(),
+            paths: Vec::with_capacity(agents.len()),
+            costs: Vec::with_capacity(agents.len()),
+        };
+        for (i, (start, goal)) in agents.iter().enumerate() {
+            let (path, cost) = self.plan(map, i, *start, *goal, &[])?;
+            root.paths.push(path);
+            root.costs.push(cost);
+        }
+
+        let mut nodes = vec![root];
+        let mut open = BinaryHeap::new();
+        open.push(OpenNode {
+            cost: nodes[0].total_cost(),
+            index: 0,
+        });
+
+        while let Some(OpenNode { index: i, .. }) = open.pop() {
+            if nodes.len() > self.max_nodes {
+                return None;
+            }
+
+            let Some(conflict) = first_conflict(&nodes[i].paths) else {
+                return Some(std::mem::take(&mut nodes[i].paths));
+            };
+
+            for constraint in conflict {
+                let node = &nodes[i];
+                let agent = constraint.agent();
+                let mut constraints = node.constraints.clone();
+                constraints.push(constraint);
+                let (start, goal) = agents[agent];
+                let Some((path, cost)) = self.plan(map, agent, start, goal, &constraints) else {
+                    continue;
+                };
+
+                let node = &nodes[i];
+                let mut paths = node.paths.clone();
+                let mut costs = node.costs.clone();
+                paths[agent] = path;
+                costs[agent] = cost;
+                let node = CtNode {
+                    constraints,
+                    paths,
+                    costs,
+                };
+                open.push(OpenNode {
+                    cost: node.total_cost(),
+                    index: nodes.len(),
+                });
+                nodes.push(node);
+            }
+        }
+        None
+    }
+
+    /// Find the cheapest path for a single agent that satisfies all of its
+    /// constraints.
+    fn plan(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        agent: usize,
+        start: IVec2,
+        goal: IVec2,
+        constraints: &[Constraint],
+    ) -> Option<(Vec<IVec2>, C)> {
+        let mut vertices = HashSet::new();
+        let mut edges = HashSet::new();
+        // The agent can't finish at its goal until after any constraints on it
+        let mut goal_time = 0;
+        for c in constraints.iter().filter(|c| c.agent() == agent) {
+            match *c {
+                Constraint::Vertex { pos, t, .. } => {
+                    vertices.insert((pos, t));
+                    if pos == goal {
+                        goal_time = goal_time.max(t + 1);
+                    }
+                }
+                Constraint::Edge { from, to, t, .. } => {
+                    edges.insert((from, to, t));
+                }
+            }
+        }
+
+        if vertices.contains(&(start, 0)) {
+            return None;
+        }
+
+        let max_time = self.max_time;
+        let path = self.search.astar(
+            (start, 0),
+            |(p, t)| p == goal && t >= goal_time,
+            |(p, t)| {
+                let vertices = &vertices;
+                let edges = &edges;
+                std::iter::once((p, map.cost(p, p)))
+                    .chain(map.exits_with_cost(p))
+                    .filter(move |(next, _)| {
+                        t < max_time
+                            && !vertices.contains(&(*next, t + 1))
+                            && !edges.contains(&(p, *next, t))
+                    })
+                    .map(move |(next, cost)| ((next, t + 1), cost))
+            },
+            |(p, _)| map.distance(goal, p),
+        )?;
+        let end = *path.last()?;
+        let path = path.iter().map(|(p, _)| *p).collect();
+        Some((path, self.search.costs()[&end]))
+    }
+}
+
+/// Find the earliest conflict between any two paths and return the pair of
+/// constraints that would resolve it.
+fn first_conflict(paths: &[Vec<IVec2>]) -> Option<[Constraint; 2]> {
+    let at = |path: &[IVec2], t: usize| path[t.min(path.len() - 1)];
+    let max_len = paths.iter().map(|p| p.len()).max().unwrap_or(0);
+
+    for t in 0..max_len {
+        for a in 0..paths.len() {
+            for b in a + 1..paths.len() {
+                let (pa, pb) = (&paths[a], &paths[b]);
+                if at(pa, t) == at(pb, t) {
+                    let pos = at(pa, t);
+                    let t = t as u32;
+                    return Some([
+                        Constraint::Vertex { agent: a, pos, t },
+                        Constraint::Vertex { agent: b, pos, t },
+                    ]);
+                }
+                let (a0, a1) = (at(pa, t), at(pa, t + 1));
+                let (b0, b1) = (at(pb, t), at(pb, t + 1));
+                if a0 != a1 && a0 == b1 && a1 == b0 {
+                    let t = t as u32;
+                    return Some([
+                        Constraint::Edge {
+                            agent: a,
+                            from: a0,
+                            to: a1,
+                            t,
+                        },
+                        Constraint::Edge {
+                            agent: b,
+                            from: b0,
+                            to: b1,
+                            t,
+                        },
+                    ]);
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::IVec2;
+
+    use super::Cbs;
+    use crate::{pathmap::Adjacency, PathMap2d};
+
+    fn assert_no_conflicts(paths: &[Vec<IVec2>]) {
+        let at = |path: &[IVec2], t: usize| path[t.min(path.len() - 1)];
+        let max_len = paths.iter().map(|p| p.len()).max().unwrap();
+        for t in 0..max_len {
+            for a in 0..paths.len() {
+                for b in a + 1..paths.len() {
+                    let (pa, pb) = (&paths[a], &paths[b]);
+                    assert_ne!(at(pa, t), at(pb, t), "Collision at {}", t);
+                    let swapped = at(pa, t) == at(pb, t + 1) && at(pa, t + 1) == at(pb, t);
+                    assert!(!swapped, "Swap at {}", t);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corridor() {
+        let mut map = PathMap2d::from_string(
+            "
+###.###
+.......
+",
+            '#',
+        )
+        .unwrap();
+        map.adjacency = Adjacency::Cardinal;
+
+        let mut cbs = Cbs::new();
+        let agents = [
+            (IVec2::new(0, 0), IVec2::new(6, 0)),
+            (IVec2::new(6, 0), IVec2::new(0, 0)),
+        ];
+        let paths = cbs.solve(&map, &agents).unwrap();
+        assert_no_conflicts(&paths);
+        assert_eq!([6, 0], paths[0].last().unwrap().to_array());
+        assert_eq!([0, 0], paths[1].last().unwrap().to_array());
+        // One agent has to step into the alcove while the other waits a turn
+        let total: usize = paths.iter().map(|p| p.len() - 1).sum();
+        assert_eq!(15, total);
+    }
+
+    #[test]
+    fn crossing() {
+        let map = PathMap2d::new([5, 5]);
+        let mut cbs = Cbs::new();
+        let agents = [
+            (IVec2::new(0, 2), IVec2::new(4, 2)),
+            (IVec2::new(4, 2), IVec2::new(0, 2)),
+            (IVec2::new(2, 0), IVec2::new(2, 4)),
+            (IVec2::new(2, 4), IVec2::new(2, 0)),
+        ];
+        let paths = cbs.solve(&map, &agents).unwrap();
+        assert_no_conflicts(&paths);
+        for (path, (start, goal)) in paths.iter().zip(agents) {
+            assert_eq!(start, path[0]);
+            assert_eq!(goal, *path.last().unwrap());
+        }
+    }
+
+    #[test]
+    fn impossible() {
+        let mut map = PathMap2d::new([5, 1]);
+        map.adjacency = Adjacency::Cardinal;
+        let mut cbs = Cbs::new().with_max_time(20).with_max_nodes(500);
+        let agents = [
+            (IVec2::new(0, 0), IVec2::new(4, 0)),
+            (IVec2::new(4, 0), IVec2::new(0, 0)),
+        ];
+        assert!(cbs.solve(&map, &agents).is_none());
+    }
+}
@@ -0,0 +1,297 @@
+//! Clearance values for pathfinding with agents that take up more than one tile,
+//! as described in [Clearance-based Pathfinding].
+//!
+//! [Clearance-based Pathfinding]: https://web.archive.org/web/20190411040123/http://aigamedev.com/open/article/clearance-based-pathfinding/
+
+use glam::{IVec2, UVec2};
+
+use crate::{
+    cost::Cost,
+    grid::SizedGrid,
+    pathmap::{PathMap, PathMap2d},
+};
+
+/// A rectangular grid storing the clearance of every position on a [PathMap2d].
+///
+/// The clearance of a position is the size of the largest square, with the
+/// position as its bottom left corner, that contains no obstacles. An agent
+/// that takes up `n` by `n` tiles can stand on any position with a clearance
+/// of at least `n`. Obstacles have a clearance of 0.
+///
+/// The clearance map must be recalculated whenever the obstacles in the
+/// [PathMap2d] change.
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::*;
+/// let mut map = PathMap2d::new([10, 10]);
+/// map.add_obstacle([5, 5]);
+/// let clearance = ClearanceMap::new(&map);
+/// assert_eq!(1, clearance.clearance([4, 4]));
+/// assert_eq!(5, clearance.clearance([0, 0]));
+/// assert_eq!(0, clearance.clearance([5, 5]));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ClearanceMap {
+    values: Vec<u32>,
+    size: UVec2,
+}
+
+impl ClearanceMap {
+    /// Create a new clearance map from the obstacles in a [PathMap2d].
+    pub fn new<C: Cost>(pathmap: &PathMap2d<C>) -> Self {
+        let mut map = Self::default();
+        map.recalculate(pathmap);
+        map
+    }
+
+    /// Recalculate all clearance values from the obstacles in a [PathMap2d].
+    pub fn recalculate<C: Cost>(&mut self, pathmap: &PathMap2d<C>) {
+        self.size = pathmap.size();
+        self.values.clear();
+        self.values.resize(self.size.element_product() as usize, 0);
+
+        let (w, h) = (self.width() as i32, self.height() as i32);
+        for y in (0..h).rev() {
+            for x in (0..w).rev() {
+                let p = IVec2::new(x, y);
+                if pathmap.is_obstacle(p) {
+                    continue;
+                }
+                let min = self
+                    .clearance(p + IVec2::X)
+                    .min(self.clearance(p + IVec2::Y))
+                    .min(self.clearance(p + IVec2::ONE));
+                let i = self.xy_to_index(p);
+                self.values[i] = min + 1;
+            }
+        }
+    }
+
+    /// The clearance at the given position. Positions outside the map have a
+    /// clearance of 0.
+    pub fn clearance(&self, p: impl Into<IVec2>) -> u32 {
+        self.try_xy_to_index(p).map(|i| self.values[i]).unwrap_or(0)
+    }
+
+    /// Whether an agent of the given size fits at the given position.
+    pub fn fits(&self, p: impl Into<IVec2>, agent_size: u32) -> bool {
+        self.clearance(p) >= agent_size
+    }
+
+    pub fn values(&self) -> &[u32] {
+        &self.values
+    }
+}
+
+impl SizedGrid for ClearanceMap {
+    fn size(&self) -> UVec2 {
+        self.size
+    }
+}
+
+/// A [PathMap] that wraps another [PathMap] and only allows movement to positions
+/// where an agent of a given size fits, based on a [ClearanceMap].
+///
+/// The agent's position is the bottom left tile of the area it takes up.
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::*;
+/// let mut map = PathMap2d::new([10, 10]);
+/// for y in 0..10 {
+///     if y != 5 {
+///         map.add_obstacle([5, y]);
+///     }
+/// }
+/// let clearance = ClearanceMap::new(&map);
+/// let mut pf = Pathfinder::new();
+/// // A 1x1 agent can fit through the gap, but a 2x2 agent can't
+/// assert!(pf.astar(&ClearancePathMap::new(&map, &clearance, 1), [0, 0], [8, 0]).is_some());
+/// assert!(pf.astar(&ClearancePathMap::new(&map, &clearance, 2), [0, 0], [8, 0]).is_none());
+/// ```
+pub struct ClearancePathMap<'a, M> {
+    map: &'a M,
+    clearance: &'a ClearanceMap,
+    agent_size: u32,
+}
+
+impl<'a, M: PathMap> ClearancePathMap<'a, M> {
+    pub fn new(map: &'a M, clearance: &'a ClearanceMap, agent_size: u32) -> Self {
+        Self {
+            map,
+            clearance,
+            agent_size,
+        }
+    }
+}
+
+impl<'a, M: PathMap> PathMap for ClearancePathMap<'a, M> {
+    type Cost = M::Cost;
+    type ExitIterator = ClearanceExits<'a, M::ExitIterator>;
+
+    fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+        ClearanceExits {
+            exits: self.map.exits(p),
+            clearance: self.clearance,
+            agent_size: self.agent_size,
+        }
+    }
+
+    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> M::Cost {
+        self.map.cost(a, b)
+    }
+
+    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> M::Cost {
+        self.map.distance(a, b)
+    }
+
+    fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+        !self.clearance.fits(p, self.agent_size)
+    }
+
+    fn cost_at(&self, a: impl Into<IVec2>, b: impl Into<IVec2>, t: u32) -> Option<M::Cost> {
+        let b = b.into();
+        if !self.clearance.fits(b, self.agent_size) {
+            return None;
+        }
+        self.map.cost_at(a, b, t)
+    }
+
+    fn exits_with_cost(&self, p: impl Into<IVec2>) -> impl Iterator<Item = (IVec2, M::Cost)> {
+        let (clearance, agent_size) = (self.clearance, self.agent_size);
+        self.map
+            .exits_with_cost(p)
+            .filter(move |(p, _)| clearance.fits(*p, agent_size))
+    }
+}
+
+/// An iterator over the exits of a [ClearancePathMap].
+pub struct ClearanceExits<'a, I> {
+    exits: I,
+    clearance: &'a ClearanceMap,
+    agent_size: u32,
+}
+
+impl<I: Iterator<Item = IVec2>> Iterator for ClearanceExits<'_, I> {
+    type Item = IVec2;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (clearance, agent_size) = (self.clearance, self.agent_size);
+        self.exits.find(|p| clearance.fits(*p, agent_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pathfinder;
+
+    #[test]
+    fn clearance_values() {
+        let map = PathMap2d::from_string(
+            "
+.....
+..#..
+.....
+.....
+",
+            '#',
+        )
+        .unwrap();
+        let clearance = ClearanceMap::new(&map);
+        assert_eq!(0, clearance.clearance([2, 2]));
+        assert_eq!(2, clearance.clearance([0, 0]));
+        assert_eq!(2, clearance.clearance([3, 0]));
+        assert_eq!(1, clearance.clearance([4, 0]));
+        assert_eq!(1, clearance.clearance([1, 1]));
+        assert_eq!(2, clearance.clearance([0, 2]));
+        assert_eq!(1, clearance.clearance([4, 3]));
+        assert_eq!(0, clearance.clearance([5, 0]));
+    }
+
+    #[test]
+    fn large_agent_path() {
+        let mut map = PathMap2d::from_string(
+            "
+..........
+..........
+....##....
+..........
+....##....
+..........
+..........
+",
+            '#',
+        )
+        .unwrap();
+        let clearance = ClearanceMap::new(&map);
+        let mut pf = Pathfinder::new();
+
+        // The small agent takes the gap between the walls
+        let small = ClearancePathMap::new(&map, &clearance, 1);
+        let path = pf.astar(&small, [0, 3], [8, 3]).unwrap();
+        assert!(path.contains(&IVec2::new(4, 3)));
+
+        // The large agent must go around
+        let large = ClearancePathMap::new(&map, &clearance, 2);
+        let path = pf.astar(&large, [0, 3], [8, 3]).unwrap();
+        for p in path {
+            for offset in [[0, 0], [1, 0], [0, 1], [1, 1]] {
+                assert!(!map.is_obstacle(*p + IVec2::from(offset)));
+            }
+        }
+
+        map.add_obstacle([4, 6]);
+        map.add_obstacle([4, 0]);
+        let clearance = ClearanceMap::new(&map);
+        let large = ClearancePathMap::new(&map, &clearance, 2);
+        assert!(pf.astar(&large, [0, 3], [8, 3]).is_none());
+    }
+
+    /// A corridor with a door at (5, 0) that's only open from time 20.
+    struct TimedDoor(PathMap2d);
+
+    impl PathMap for TimedDoor {
+        type Cost = i32;
+        type ExitIterator = <PathMap2d as PathMap>::ExitIterator;
+
+        fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+            self.0.exits(p)
+        }
+
+        fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
+            self.0.cost(a, b)
+        }
+
+        fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
+            self.0.distance(a, b)
+        }
+
+        fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+            self.0.is_obstacle(p)
+        }
+
+        fn cost_at(&self, a: impl Into<IVec2>, b: impl Into<IVec2>, t: u32) -> Option<i32> {
+            let (a, b) = (a.into(), b.into());
+            let cost = self.cost(a, b);
+            (b != IVec2::new(5, 0) || t + cost as u32 >= 20).then_some(cost)
+        }
+    }
+
+    #[test]
+    fn timed_costs() {
+        let mut map = PathMap2d::new([10, 1]);
+        map.adjacency = crate::pathmap::Adjacency::Cardinal;
+        let clearance = ClearanceMap::new(&map);
+        let map = TimedDoor(map);
+        let mut pf = Pathfinder::new();
+
+        let expected = pf.astar_timed(&map, [0, 0], [9, 0], 0, 100).unwrap().len();
+        let wrapped = ClearancePathMap::new(&map, &clearance, 1);
+        let path = pf.astar_timed(&wrapped, [0, 0], [9, 0], 0, 100).unwrap();
+        assert_eq!(expected, path.len());
+        assert!(path.len() > 10);
+        assert_eq!(24, *pf.arrival_times().last().unwrap());
+    }
+}
@@ -0,0 +1,129 @@
+//! The numeric types that can be used for movement costs.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+/// A numeric type that can be used for movement costs and distances in a
+/// [crate::PathMap].
+///
+/// Implemented for `i32`, `i64`, `u32`, `f32` and `f64`. Floating point costs
+/// are compared with a total ordering, and costs must never be NaN. A fixed
+/// point type can be used by implementing this trait for it.
+pub trait Cost:
+    Copy + Debug + PartialEq + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Convert from an integer, such as a number of steps.
+    fn from_i32(v: i32) -> Self;
+
+    /// Convert from a float, rounding towards zero for integer costs.
+    fn from_f32(v: f32) -> Self;
+
+    fn to_f32(self) -> f32;
+
+    /// Compare two costs with a total ordering.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+
+    /// The smaller of two costs.
+    fn min_cost(self, other: Self) -> Self {
+        match self.total_cmp(&other) {
+            Ordering::Greater => other,
+            _ => self,
+        }
+    }
+
+    /// The larger of two costs.
+    fn max_cost(self, other: Self) -> Self {
+        match self.total_cmp(&other) {
+            Ordering::Less => other,
+            _ => self,
+        }
+    }
+}
+
+macro_rules! impl_int_cost {
+    ($($t:ty),*) => {
+        $(
+            impl Cost for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn from_i32(v: i32) -> Self {
+                    v as $t
+                }
+
+                fn from_f32(v: f32) -> Self {
+                    v as $t
+                }
+
+                fn to_f32(self) -> f32 {
+                    self as f32
+                }
+
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    self.cmp(other)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float_cost {
+    ($($t:ty),*) => {
+        $(
+            impl Cost for $t {
+                const ZERO: Self = 0.0;
+                const ONE: Self = 1.0;
+
+                fn from_i32(v: i32) -> Self {
+                    v as $t
+                }
+
+                fn from_f32(v: f32) -> Self {
+                    v as $t
+                }
+
+                fn to_f32(self) -> f32 {
+                    self as f32
+                }
+
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    <$t>::total_cmp(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_int_cost!(i32, i64, u32);
+impl_float_cost!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_f32_truncates() {
+        assert_eq!(14, i32::from_f32(10.0 * std::f32::consts::SQRT_2));
+        assert_eq!(1, u32::from_f32(1.99));
+        assert_eq!(-1, i64::from_f32(-1.5));
+        assert_eq!(0, u32::from_f32(-3.0));
+        assert_eq!(1.5, f32::from_f32(1.5));
+    }
+
+    #[test]
+    fn total_cmp_nan() {
+        assert_eq!(Ordering::Less, Cost::total_cmp(&1.0, &f32::NAN));
+        assert_eq!(
+            Ordering::Greater,
+            Cost::total_cmp(&f32::NAN, &f32::INFINITY)
+        );
+        assert_eq!(Ordering::Equal, Cost::total_cmp(&f64::NAN, &f64::NAN));
+        assert_eq!(1.0, 1.0.min_cost(f32::NAN));
+        assert!(f32::NAN.max_cost(1.0).is_nan());
+        assert_eq!(Ordering::Less, Cost::total_cmp(&-1, &2));
+    }
+}
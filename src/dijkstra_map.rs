@@ -2,6 +2,7 @@
 
 use crate::{
     bit_grid::BitGrid,
+    cost::Cost,
     float_grid::FloatGrid,
     grid::{Grid, SizedGrid},
     min_heap::MinHeap,
@@ -12,7 +13,10 @@ use arrayvec::{ArrayVec, IntoIter};
 use glam::{IVec2, UVec2};
 
 const INITIAL_VALUE: f32 = 1000.0;
-const EXIT_CAP: usize = 8;
+
+/// The maximum number of exits returned by [DijkstraMap::exit_values]. Room
+/// for eight adjacent exits plus eight links.
+pub const EXIT_CAP: usize = 16;
 
 /// A simple implementation of a "Dijkstra Map" as described in [Dijsktra Maps Visualized]
 ///
@@ -48,8 +52,11 @@ pub struct DijkstraMap {
     /// Obstacles are only ever populated during recalculation by the provided
     /// path map and are only used during iteration to skip obtacles.
     obstacles: BitGrid,
-    frontier: MinHeap,
+    frontier: MinHeap<f32>,
     initial_value: f32,
+    /// The tiles reached by the last [DijkstraMap::recalculate_within], if no
+    /// other tiles have been changed since.
+    within: Option<Vec<IVec2>>,
 }
 
 impl DijkstraMap {
@@ -63,12 +70,14 @@ impl DijkstraMap {
             frontier: MinHeap::with_capacity(size.element_product() as usize),
             obstacles: BitGrid::new(size),
             initial_value: INITIAL_VALUE,
+            within: None,
         }
     }
 
     pub fn with_initial_value(mut self, initial_value: f32) -> Self {
         self.initial_value = initial_value;
         self.value_grid.set_all(initial_value);
+        self.within = None;
         self
     }
 
@@ -113,6 +122,7 @@ impl DijkstraMap {
             obstacles,
             frontier: MinHeap::with_capacity(size.element_product() as usize),
             initial_value: INITIAL_VALUE,
+            within: None,
         })
     }
 
@@ -131,6 +141,7 @@ impl DijkstraMap {
             self.value_grid[xy] = value;
             self.goals.insert(xy);
         }
+        self.touch(xy);
     }
 
     /// Set the goal value for a position.
@@ -140,6 +151,14 @@ impl DijkstraMap {
         let xy = xy.into();
         self.value_grid[xy] = value;
         self.goals.insert(xy);
+        self.touch(xy);
+    }
+
+    /// Record that a tile's value was changed outside of a recalculation.
+    fn touch(&mut self, xy: IVec2) {
+        if let Some(within) = &mut self.within {
+            within.push(xy);
+        }
     }
 
     /// Recalculate the map based on the given pathing.
@@ -148,6 +167,7 @@ impl DijkstraMap {
     /// values. To recalculate a clean map based only on the currently set goals,
     /// call [DijkstraMap::clear_values] to clear any previously set non-goal tiles.
     pub fn recalculate(&mut self, pathing: &impl PathMap) {
+        self.within = None;
         self.obstacles.set_all(true);
         self.frontier.clear();
 
@@ -157,19 +177,74 @@ impl DijkstraMap {
             if !self.goals.contains(&xy) && pathing.is_obstacle(xy) {
                 continue;
             }
-            self.frontier.push(xy, self.value_grid.values()[i] as i32);
+            self.frontier.push(xy, self.value_grid.values()[i]);
         }
 
         while let Some(curr) = self.frontier.pop() {
-            for next in pathing.exits(curr) {
-                let new_cost = self.value_grid[curr] + pathing.cost(curr, next) as f32;
+            for (next, cost) in pathing.exits_with_cost(curr) {
+                let new_cost = self.value_grid[curr] + cost.to_f32();
                 self.obstacles.set(next, false);
                 if new_cost < self.value_grid[next] {
                     self.value_grid[next] = new_cost;
-                    self.frontier.push(next, new_cost as i32);
+                    self.frontier.push(next, new_cost);
+                }
+            }
+        }
+    }
+
+    /// Recalculate the map based on the given pathing, only propagating values
+    /// outwards from the goals until they would exceed `max_cost`.
+    ///
+    /// Unlike [DijkstraMap::recalculate] only the goal tiles are used as a
+    /// starting point. Tiles that are out of range are left at the initial value
+    /// and are skipped by [DijkstraMap::iter_xy].
+    ///
+    /// Only the tiles reached by the previous call are reset, so repeated calls
+    /// take time depending on the area within range of the goals rather than the
+    /// size of the map. The first call, or a call after the map was changed some
+    /// other way such as by [DijkstraMap::recalculate], resets the whole map.
+    pub fn recalculate_within(&mut self, pathing: &impl PathMap, max_cost: f32) {
+        let mut touched = match self.within.take() {
+            Some(mut touched) => {
+                for p in touched.drain(..) {
+                    if !self.goals.contains(&p) {
+                        self.value_grid[p] = self.initial_value;
+                    }
+                    self.obstacles.set(p, true);
+                }
+                touched
+            }
+            None => {
+                self.clear_values();
+                self.obstacles.set_all(true);
+                Vec::new()
+            }
+        };
+        self.frontier.clear();
+
+        for &goal in self.goals.iter() {
+            self.obstacles.set(goal, false);
+            self.frontier.push(goal, self.value_grid[goal]);
+            touched.push(goal);
+        }
+
+        while let Some(curr) = self.frontier.pop() {
+            for (next, cost) in pathing.exits_with_cost(curr) {
+                let new_cost = self.value_grid[curr] + cost.to_f32();
+                if new_cost > max_cost {
+                    continue;
+                }
+                if self.obstacles.get(next) {
+                    self.obstacles.set(next, false);
+                    touched.push(next);
+                }
+                if new_cost < self.value_grid[next] {
+                    self.value_grid[next] = new_cost;
+                    self.frontier.push(next, new_cost);
                 }
             }
         }
+        self.within = Some(touched);
     }
 
     /// Remove a goal. This will not affect any previously set value
@@ -185,6 +260,7 @@ impl DijkstraMap {
 
     /// Resets the value of all non-goal tiles.
     pub fn clear_values(&mut self) {
+        self.within = None;
         for (p, v) in self.value_grid.iter_xy_mut() {
             if !self.goals.contains(&p) {
                 *v = self.initial_value;
@@ -201,6 +277,7 @@ impl DijkstraMap {
 
     /// Apply a mathematical operation to every value in the map.
     pub fn apply_operation(&mut self, operation: impl Fn(f32) -> f32) {
+        self.within = None;
         self.value_grid.apply_operation(operation);
     }
 
@@ -211,6 +288,7 @@ impl DijkstraMap {
 
     /// A mutable reference to the [DijkstraMap]'s underlying [FloatGrid].
     pub fn float_grid_mut(&mut self) -> &mut FloatGrid {
+        self.within = None;
         &mut self.value_grid
     }
 
@@ -234,20 +312,23 @@ impl DijkstraMap {
     ///
     /// Returns an iterator of 2 element tuples, where each tuple contains a position
     /// and it's corresponding 'value' in the [DijkstraMap]. The exits
-    /// are sorted by 'most valuable' first before being returned.
+    /// are sorted by 'most valuable' first before being returned. At most
+    /// [EXIT_CAP] exits are returned, any less valuable exits beyond that are
+    /// ignored.
     pub fn exit_values(
         &self,
         xy: impl Into<IVec2>,
         pathing: &impl PathMap,
     ) -> IntoIter<(IVec2, i32), EXIT_CAP> {
-        let xy = xy.into();
-        let mut v = ArrayVec::new();
-        for next in pathing.exits(xy) {
-            let Some(i) = self.value_grid.try_xy_to_index(next) else {
-                continue;
-            };
-
-            v.push((next, self.value_grid.values()[i] as i32));
+        let mut v: ArrayVec<_, EXIT_CAP> = ArrayVec::new();
+        for exit in pathing.exits(xy).filter_map(|p| self.exit_value(p)) {
+            if let Err(err) = v.try_push(exit) {
+                // Keep the most valuable exits by replacing the least valuable
+                let worst = v.iter_mut().max_by_key(|a| a.1).unwrap();
+                if err.element().1 < worst.1 {
+                    *worst = err.element();
+                }
+            }
         }
         v.sort_unstable_by_key(|a| a.1);
 
@@ -258,36 +339,27 @@ impl DijkstraMap {
     ///
     /// The [DijkstraMap] does not store pathing information so a [PathMap] must be provided.
     pub fn next_lowest(&self, xy: impl Into<IVec2>, pathing: &impl PathMap) -> Option<IVec2> {
-        let xy = xy.into();
-        let mut v: ArrayVec<(IVec2, i32), EXIT_CAP> = ArrayVec::new();
-        for next in pathing.exits(xy) {
-            let Some(i) = self.value_grid.try_xy_to_index(next) else {
-                continue;
-            };
-
-            v.push((next, self.value_grid.values()[i] as i32));
-        }
-        v.sort_unstable_by_key(|a| a.1);
-
-        v.into_iter().next().map(|pv| pv.0)
+        pathing
+            .exits(xy)
+            .filter_map(|p| self.exit_value(p))
+            .min_by_key(|a| a.1)
+            .map(|pv| pv.0)
     }
 
     /// Returns the highest value exit from a position if there is one.
     ///
     /// The [DijkstraMap] does not store pathing information so a [PathMap] must be provided.
     pub fn next_highest(&self, xy: impl Into<IVec2>, pathing: &impl PathMap) -> Option<IVec2> {
-        let xy = xy.into();
-        let mut v: ArrayVec<(IVec2, i32), EXIT_CAP> = ArrayVec::new();
-        for next in pathing.exits(xy) {
-            let Some(i) = self.value_grid.try_xy_to_index(next) else {
-                continue;
-            };
-
-            v.push((next, self.value_grid.values()[i] as i32));
-        }
-        v.sort_unstable_by_key(|b| std::cmp::Reverse(b.1));
+        pathing
+            .exits(xy)
+            .filter_map(|p| self.exit_value(p))
+            .max_by_key(|a| a.1)
+            .map(|pv| pv.0)
+    }
 
-        v.into_iter().next().map(|pv| pv.0)
+    fn exit_value(&self, next: IVec2) -> Option<(IVec2, i32)> {
+        let i = self.value_grid.try_xy_to_index(next)?;
+        Some((next, self.value_grid.values()[i] as i32))
     }
 
     pub fn values(&self) -> &[f32] {
@@ -336,8 +408,8 @@ impl SizedGrid for DijkstraMap {
 mod tests {
     use glam::UVec2;
 
-    use super::DijkstraMap;
-    use crate::PathMap2d;
+    use super::{DijkstraMap, EXIT_CAP};
+    use crate::{pathmap::Adjacency, PathMap, PathMap2d};
 
     #[test]
     #[ignore]
@@ -393,6 +465,55 @@ mod tests {
         map.print_grid_values();
     }
 
+    #[test]
+    fn within() {
+        let size = UVec2::splat(30);
+        let mut map = DijkstraMap::new(size);
+        let mut pathing = PathMap2d::new(size);
+        pathing.adjacency = Adjacency::Cardinal;
+        map.add_goal([5, 5], 0.0);
+        map.recalculate_within(&pathing, 10.0);
+
+        assert_eq!(0.0, map.float_grid()[[5, 5]]);
+        assert_eq!(4.0, map.float_grid()[[7, 7]]);
+        assert_eq!(10.0, map.float_grid()[[15, 5]]);
+        assert_eq!(1000.0, map.float_grid()[[16, 5]]);
+        assert_eq!(1000.0, map.float_grid()[[20, 20]]);
+        assert!(map.iter_xy().all(|(_, v)| *v <= 10.0));
+    }
+
+    #[test]
+    fn within_twice() {
+        let size = UVec2::splat(30);
+        let mut map = DijkstraMap::new(size);
+        let mut pathing = PathMap2d::new(size);
+        pathing.adjacency = Adjacency::Cardinal;
+        map.add_goal([5, 5], 0.0);
+        map.recalculate_within(&pathing, 10.0);
+
+        map.remove_goal([5, 5]);
+        map.add_goal([20, 20], 0.0);
+        map.recalculate_within(&pathing, 10.0);
+
+        assert_eq!(1000.0, map.float_grid()[[5, 5]]);
+        assert_eq!(1000.0, map.float_grid()[[7, 7]]);
+        assert_eq!(0.0, map.float_grid()[[20, 20]]);
+        assert_eq!(4.0, map.float_grid()[[22, 22]]);
+        assert!(map
+            .iter_xy()
+            .all(|(p, _)| (p - glam::IVec2::splat(20)).abs().element_sum() <= 10));
+
+        // Only the tiles reached by the last run are recorded to be reset
+        let reached = map.iter_xy().count();
+        assert_eq!(Some(reached), map.within.as_ref().map(|w| w.len()));
+
+        // A full recalculation in between means everything is reset again
+        map.recalculate(&pathing);
+        map.recalculate_within(&pathing, 2.0);
+        assert_eq!(1000.0, map.float_grid()[[5, 5]]);
+        assert_eq!(13, map.iter_xy().count());
+    }
+
     #[test]
     #[ignore]
     fn string_map() {
@@ -413,4 +534,25 @@ mod tests {
         map.recalculate(&pathing);
         map.print_grid_values();
     }
+
+    #[test]
+    fn many_exits() {
+        let size = UVec2::splat(40);
+        let mut pathing = PathMap2d::new(size);
+        // The most valuable exit is the last one
+        for x in 0..10 {
+            pathing.add_link([20, 20], [20 + x, 30], 1);
+        }
+        for y in (20..30).rev() {
+            pathing.add_link([20, 20], [30, y], 1);
+        }
+        let mut map = DijkstraMap::new(size);
+        map.add_goal([39, 20], 0.0);
+        map.recalculate(&pathing);
+
+        assert!(pathing.exits([20, 20]).count() > EXIT_CAP);
+        let best = map.exit_values([20, 20], &pathing).next().unwrap().0;
+        assert_eq!(map.next_lowest([20, 20], &pathing), Some(best));
+        assert_eq!(EXIT_CAP, map.exits([20, 20], &pathing).count());
+    }
 }
@@ -0,0 +1,196 @@
+//! A [PathMap] built from closures, for adapting existing map types without
+//! implementing the trait by hand.
+
+use glam::IVec2;
+
+use crate::{cost::Cost, pathmap::PathMap};
+
+/// A [PathMap] built from closures.
+///
+/// Only the `exits` closure is required, which should return any iterable of
+/// the positions that can be reached from the given position. By default every
+/// move costs 1, the distance heuristic is always 0 and there are no obstacles.
+/// The [Cost] type is whatever the cost closure returns, and the default
+/// distance of [ZeroDistance] works with any [Cost] type.
+/// These can be replaced with [FnPathMap::with_cost], [FnPathMap::with_distance]
+/// and [FnPathMap::with_obstacles].
+///
+/// # Example
+/// ```
+/// use glam::IVec2;
+/// use sark_pathfinding::*;
+/// let walls = [IVec2::new(1, 0), IVec2::new(1, 1)];
+/// let map = FnPathMap::new(|p: IVec2| {
+///     [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+///         .map(|d| p + d)
+///         .into_iter()
+///         .filter(move |p| !walls.contains(p) && p.cmpge(IVec2::ZERO).all() && p.cmplt(IVec2::splat(5)).all())
+/// })
+/// .with_distance(|a, b| (a - b).abs().element_sum())
+/// .with_obstacles(|p| walls.contains(&p));
+///
+/// let mut pf = Pathfinder::new();
+/// let path = pf.astar(&map, [0, 0], [2, 0]).unwrap();
+/// assert_eq!(7, path.len());
+/// ```
+#[derive(Clone)]
+pub struct FnPathMap<E, C = fn(IVec2, IVec2) -> i32, D = ZeroDistance, O = fn(IVec2) -> bool> {
+    exits: E,
+    cost: C,
+    distance: D,
+    is_obstacle: O,
+}
+
+/// The heuristic distance between two positions in a [FnPathMap].
+///
+/// This is implemented for any `Fn(IVec2, IVec2) -> K` closure, and for
+/// [ZeroDistance].
+pub trait FnDistance<K> {
+    fn distance(&self, a: IVec2, b: IVec2) -> K;
+}
+
+impl<F: Fn(IVec2, IVec2) -> K, K> FnDistance<K> for F {
+    fn distance(&self, a: IVec2, b: IVec2) -> K {
+        self(a, b)
+    }
+}
+
+/// The default [FnPathMap] distance, which is always zero for any [Cost] type.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZeroDistance;
+
+impl<K: Cost> FnDistance<K> for ZeroDistance {
+    fn distance(&self, _: IVec2, _: IVec2) -> K {
+        K::ZERO
+    }
+}
+
+impl<E, I> FnPathMap<E>
+where
+    E: Fn(IVec2) -> I,
+    I: IntoIterator<Item = IVec2>,
+{
+    /// Create a new map from a closure returning the exits from a position.
+    pub fn new(exits: E) -> Self {
+        Self {
+            exits,
+            cost: |_, _| 1,
+            distance: ZeroDistance,
+            is_obstacle: |_| false,
+        }
+    }
+}
+
+impl<E, C, D, O> FnPathMap<E, C, D, O> {
+    /// Set the closure returning the cost of moving between two positions.
+    pub fn with_cost<C2, K>(self, cost: C2) -> FnPathMap<E, C2, D, O>
+    where
+        C2: Fn(IVec2, IVec2) -> K,
+    {
+        FnPathMap {
+            exits: self.exits,
+            cost,
+            distance: self.distance,
+            is_obstacle: self.is_obstacle,
+        }
+    }
+
+    /// Set the closure returning the heuristic distance between two positions.
+    /// It must never overestimate the real cost of moving between them.
+    pub fn with_distance<D2, K>(self, distance: D2) -> FnPathMap<E, C, D2, O>
+    where
+        D2: Fn(IVec2, IVec2) -> K,
+    {
+        FnPathMap {
+            exits: self.exits,
+            cost: self.cost,
+            distance,
+            is_obstacle: self.is_obstacle,
+        }
+    }
+
+    /// Set the closure returning whether a position is an obstacle.
+    pub fn with_obstacles<O2>(self, is_obstacle: O2) -> FnPathMap<E, C, D, O2>
+    where
+        O2: Fn(IVec2) -> bool,
+    {
+        FnPathMap {
+            exits: self.exits,
+            cost: self.cost,
+            distance: self.distance,
+            is_obstacle,
+        }
+    }
+}
+
+impl<E, I, C, D, O, K> PathMap for FnPathMap<E, C, D, O>
+where
+    E: Fn(IVec2) -> I,
+    I: IntoIterator<Item = IVec2>,
+    C: Fn(IVec2, IVec2) -> K,
+    D: FnDistance<K>,
+    O: Fn(IVec2) -> bool,
+    K: Cost,
+{
+    type Cost = K;
+    type ExitIterator = I::IntoIter;
+
+    fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+        (self.exits)(p.into()).into_iter()
+    }
+
+    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> K {
+        (self.cost)(a.into(), b.into())
+    }
+
+    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> K {
+        self.distance.distance(a.into(), b.into())
+    }
+
+    fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+        (self.is_obstacle)(p.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PathMap2d, Pathfinder};
+
+    #[test]
+    fn matches_pathmap2d() {
+        let mut grid = PathMap2d::new([20, 20]);
+        for y in 0..15 {
+            grid.add_obstacle([8, y]);
+        }
+        let map = FnPathMap::new(|p| grid.exits(p))
+            .with_cost(|a, b| grid.cost(a, b))
+            .with_distance(|a, b| grid.distance(a, b))
+            .with_obstacles(|p| grid.is_obstacle(p));
+        assert!(map.is_obstacle(IVec2::new(8, 0)));
+
+        let mut pf = Pathfinder::new();
+        let expected = pf.astar(&grid, [0, 0], [19, 0]).unwrap().to_vec();
+        let path = pf.astar(&map, [0, 0], [19, 0]).unwrap();
+        assert_eq!(expected, path);
+    }
+
+    #[test]
+    fn defaults() {
+        // A line graph where each position leads to the next
+        let map = FnPathMap::new(|p: IVec2| (p.x < 10).then_some(p + IVec2::X));
+        let mut pf = Pathfinder::new();
+        pf.astar(&map, [0, 0], [10, 0]).unwrap();
+        assert_eq!(10, pf.costs()[&IVec2::new(10, 0)]);
+        assert!(pf.astar(&map, [10, 0], [0, 0]).is_none());
+    }
+
+    #[test]
+    fn float_costs() {
+        let map =
+            FnPathMap::new(|p: IVec2| (p.x < 4).then_some(p + IVec2::X)).with_cost(|_, _| 1.5f32);
+        let mut pf = Pathfinder::new();
+        pf.astar(&map, [0, 0], [4, 0]).unwrap();
+        assert_eq!(6.0, pf.costs()[&IVec2::new(4, 0)]);
+    }
+}
@@ -0,0 +1,233 @@
+//! Heuristics which estimate the remaining cost to a goal during an A* search,
+//! for use with [crate::Pathfinder::astar_with_heuristic].
+//!
+//! For the path found to be the cheapest, a heuristic must never overestimate
+//! the real cost of moving between two positions on the map being searched.
+
+use std::cell::RefCell;
+
+use ahash::HashMap;
+use glam::IVec2;
+
+use crate::{
+    cost::Cost,
+    landmarks::Landmarks,
+    pathmap::{octile_distance, DEFAULT_CARDINAL_COST, DEFAULT_DIAGONAL_COST},
+};
+
+/// An estimate of the cost of moving from a position to a goal, in the [Cost]
+/// type `C` of the map being searched.
+///
+/// Implemented for any `Fn(IVec2, IVec2) -> C` closure.
+pub trait Heuristic<C = i32> {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C;
+}
+
+impl<C: Cost, F: Fn(IVec2, IVec2) -> C> Heuristic<C> for F {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C {
+        self(p, goal)
+    }
+}
+
+/// The [taxicab distance](https://en.wikipedia.org/wiki/Taxicab_geometry)
+/// multiplied by the cost of a single move. Suitable for four-way movement.
+#[derive(Debug, Clone, Copy)]
+pub struct Manhattan<C = i32> {
+    pub cost: C,
+}
+
+impl<C: Cost> Default for Manhattan<C> {
+    fn default() -> Self {
+        Self { cost: C::ONE }
+    }
+}
+
+impl<C: Cost> Heuristic<C> for Manhattan<C> {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C {
+        let d = (p - goal).abs();
+        C::from_i32(d.x + d.y) * self.cost
+    }
+}
+
+/// The cost of the shortest path on an open eight-way grid with separate costs
+/// for cardinal and diagonal moves.
+#[derive(Debug, Clone, Copy)]
+pub struct Octile<C = i32> {
+    pub cardinal_cost: C,
+    pub diagonal_cost: C,
+}
+
+impl<C: Cost> Default for Octile<C> {
+    fn default() -> Self {
+        Self {
+            cardinal_cost: C::from_i32(DEFAULT_CARDINAL_COST),
+            diagonal_cost: C::from_i32(DEFAULT_DIAGONAL_COST),
+        }
+    }
+}
+
+impl<C: Cost> Heuristic<C> for Octile<C> {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C {
+        octile_distance(p, goal, self.cardinal_cost, self.diagonal_cost)
+    }
+}
+
+/// The [Chebyshev distance](https://en.wikipedia.org/wiki/Chebyshev_distance)
+/// multiplied by the cost of a single move. Suitable for eight-way movement
+/// where diagonal moves cost the same as cardinal moves.
+#[derive(Debug, Clone, Copy)]
+pub struct Chebyshev<C = i32> {
+    pub cost: C,
+}
+
+impl<C: Cost> Default for Chebyshev<C> {
+    fn default() -> Self {
+        Self { cost: C::ONE }
+    }
+}
+
+impl<C: Cost> Heuristic<C> for Chebyshev<C> {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C {
+        C::from_i32((p - goal).abs().max_element()) * self.cost
+    }
+}
+
+/// The straight line distance multiplied by the cost of moving one tile,
+/// rounded down for integer costs.
+#[derive(Debug, Clone, Copy)]
+pub struct Euclidean {
+    pub cost: f32,
+}
+
+impl Default for Euclidean {
+    fn default() -> Self {
+        Self { cost: 1.0 }
+    }
+}
+
+impl<C: Cost> Heuristic<C> for Euclidean {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C {
+        C::from_f32(p.as_vec2().distance(goal.as_vec2()) * self.cost)
+    }
+}
+
+/// A heuristic that always returns 0, which turns A* into Dijkstra's algorithm.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Zero;
+
+impl<C: Cost> Heuristic<C> for Zero {
+    fn estimate(&self, _: IVec2, _: IVec2) -> C {
+        C::ZERO
+    }
+}
+
+impl<C: Cost> Heuristic<C> for Landmarks<C> {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C {
+        Landmarks::estimate(self, p, goal)
+    }
+}
+
+/// Wraps another heuristic and remembers every estimate it makes, for
+/// heuristics that are expensive to calculate.
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::{heuristic::*, *};
+/// let map = PathMap2d::new([10, 10]);
+/// let heuristic = Cached::new(Octile::default());
+/// let mut pf = Pathfinder::new();
+/// pf.astar_with_heuristic(&map, [0, 0], [9, 9], &heuristic).unwrap();
+/// pf.astar_with_heuristic(&map, [0, 0], [9, 9], &heuristic).unwrap();
+/// assert!(heuristic.len() > 0);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Cached<H, C = i32> {
+    heuristic: H,
+    cache: RefCell<HashMap<(IVec2, IVec2), C>>,
+}
+
+impl<H: Heuristic<C>, C: Cost> Cached<H, C> {
+    pub fn new(heuristic: H) -> Self {
+        Self {
+            heuristic,
+            cache: RefCell::default(),
+        }
+    }
+
+    /// The number of cached estimates.
+    pub fn len(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.borrow().is_empty()
+    }
+
+    /// Remove all cached estimates.
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear();
+    }
+}
+
+impl<H: Heuristic<C>, C: Cost> Heuristic<C> for Cached<H, C> {
+    fn estimate(&self, p: IVec2, goal: IVec2) -> C {
+        *self
+            .cache
+            .borrow_mut()
+            .entry((p, goal))
+            .or_insert_with(|| self.heuristic.estimate(p, goal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pathmap::Adjacency, PathMap2d, Pathfinder};
+
+    #[test]
+    fn same_cost_as_astar() {
+        let mut map = PathMap2d::new([20, 20]);
+        for y in 2..20 {
+            map.add_obstacle([10, y]);
+        }
+        let (start, goal) = (IVec2::new(0, 10), IVec2::new(19, 10));
+        let mut pf = Pathfinder::new();
+        pf.astar(&map, start, goal).unwrap();
+        let expected = pf.costs()[&goal];
+
+        let heuristics: [&dyn Heuristic; 5] = [
+            &Octile::default(),
+            &Euclidean { cost: 2.0 },
+            &Zero,
+            &Cached::new(Octile::default()),
+            &|_: IVec2, _: IVec2| 0,
+        ];
+        for h in heuristics {
+            pf.astar_with_heuristic(&map, start, goal, h).unwrap();
+            assert_eq!(expected, pf.costs()[&goal]);
+        }
+
+        // A more informed heuristic visits fewer positions
+        pf.astar_with_heuristic(&map, start, goal, &Zero).unwrap();
+        let zero = pf.costs().len();
+        pf.astar_with_heuristic(&map, start, goal, &Octile::default())
+            .unwrap();
+        assert!(pf.costs().len() < zero);
+    }
+
+    #[test]
+    fn grid_distances() {
+        let (a, b) = (IVec2::new(1, 1), IVec2::new(4, 5));
+        assert_eq!(7, Manhattan::default().estimate(a, b));
+        assert_eq!(4, Chebyshev::default().estimate(a, b));
+        assert_eq!(5, Euclidean::default().estimate(a, b));
+        assert_eq!(11, Octile::default().estimate(a, b));
+
+        let mut map = PathMap2d::new([10, 10]);
+        map.adjacency = Adjacency::Cardinal;
+        let mut pf = Pathfinder::new();
+        pf.astar_with_heuristic(&map, a, b, &Manhattan::default())
+            .unwrap();
+        assert_eq!(7, pf.costs()[&b]);
+    }
+}
@@ -0,0 +1,257 @@
+//! Landmark based heuristics, also known as [ALT], which give A* a much better
+//! estimate of the remaining cost on maps with lots of walls or dead ends.
+//!
+//! [ALT]: https://www.redblobgames.com/pathfinding/heuristics/differential.html
+
+use ahash::HashMap;
+use glam::IVec2;
+
+use crate::{cost::Cost, pathfinder::Pathfinder, pathmap::PathMap};
+
+/// Precomputed path costs from a set of landmark positions to every reachable
+/// position on a map.
+///
+/// By the triangle inequality the cost of a path from `a` to `b` is at least
+/// `cost(landmark, b) - cost(landmark, a)` for every landmark. Landmarks work
+/// best when spread out around the edges of the map, see [Landmarks::farthest].
+///
+/// The tables must be rebuilt whenever the map changes.
+#[derive(Debug, Clone)]
+pub struct Landmarks<C = i32> {
+    landmarks: Vec<IVec2>,
+    costs: Vec<HashMap<IVec2, C>>,
+}
+
+impl<C> Default for Landmarks<C> {
+    fn default() -> Self {
+        Self {
+            landmarks: Vec::new(),
+            costs: Vec::new(),
+        }
+    }
+}
+
+impl<C: Cost> Landmarks<C> {
+    /// Calculate the cost tables for the given landmark positions.
+    pub fn new(
+        map: &impl PathMap<Cost = C>,
+        landmarks: impl IntoIterator<Item = impl Into<IVec2>>,
+    ) -> Self {
+        let mut pf = Pathfinder::new();
+        let mut this = Self::default();
+        for landmark in landmarks {
+            this.add(&mut pf, map, landmark);
+        }
+        this
+    }
+
+    /// Choose `count` landmarks spread out across the part of the map reachable
+    /// from `start`, where each landmark is the position furthest from all the
+    /// landmarks before it, and calculate their cost tables.
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::{landmarks::*, *};
+    /// let map = PathMap2d::new([10, 10]);
+    /// let landmarks = Landmarks::farthest(&map, [0, 0], 2);
+    /// assert_eq!([9, 9], landmarks.landmarks()[0].to_array());
+    /// assert_eq!([0, 0], landmarks.landmarks()[1].to_array());
+    /// ```
+    pub fn farthest(map: &impl PathMap<Cost = C>, start: impl Into<IVec2>, count: usize) -> Self {
+        let mut pf = Pathfinder::new();
+        let mut this = Self::default();
+        pf.dijkstra(map, None::<IVec2>, start);
+        // The minimum cost from any landmark to each position
+        let mut nearest: HashMap<IVec2, C> = pf.costs().clone();
+        for _ in 0..count {
+            let Some((next, _)) = nearest
+                .iter()
+                .filter(|(p, _)| !this.landmarks.contains(p))
+                .max_by(|(a, ca), (b, cb)| {
+                    ca.total_cmp(cb).then(a.y.cmp(&b.y)).then(a.x.cmp(&b.x))
+                })
+            else {
+                break;
+            };
+            let next = *next;
+            this.add(&mut pf, map, next);
+            let costs = this.costs.last().unwrap();
+            for (p, c) in nearest.iter_mut() {
+                if this.landmarks.len() == 1 {
+                    *c = costs.get(p).copied().unwrap_or(*c);
+                } else if let Some(lc) = costs.get(p) {
+                    *c = c.min_cost(*lc);
+                }
+            }
+        }
+        this
+    }
+
+    fn add(
+        &mut self,
+        pf: &mut Pathfinder<C>,
+        map: &impl PathMap<Cost = C>,
+        landmark: impl Into<IVec2>,
+    ) {
+        let landmark = landmark.into();
+        pf.dijkstra(map, None::<IVec2>, landmark);
+        self.landmarks.push(landmark);
+        self.costs.push(pf.costs().clone());
+    }
+
+    /// The landmark positions.
+    pub fn landmarks(&self) -> &[IVec2] {
+        &self.landmarks
+    }
+
+    /// The cost of the cheapest path from a landmark to a position, if it can
+    /// be reached.
+    pub fn cost(&self, landmark: usize, p: impl Into<IVec2>) -> Option<C> {
+        self.costs[landmark].get(&p.into()).copied()
+    }
+
+    /// A lower bound on the cost of a path from `p` to `goal`, which is
+    /// admissible even when moves cost different amounts in each direction.
+    /// Suitable as the heuristic for [Pathfinder::astar_fn].
+    pub fn estimate(&self, p: impl Into<IVec2>, goal: impl Into<IVec2>) -> C {
+        let (p, goal) = (p.into(), goal.into());
+        self.costs
+            .iter()
+            .filter_map(|costs| Some(difference(*costs.get(&goal)?, *costs.get(&p)?)))
+            .fold(C::ZERO, C::max_cost)
+    }
+
+    /// A lower bound on the cost of a path between `a` and `b` in either
+    /// direction. Only admissible if every move costs the same in both
+    /// directions.
+    pub fn symmetric_estimate(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> C {
+        let (a, b) = (a.into(), b.into());
+        self.costs
+            .iter()
+            .filter_map(|costs| {
+                let (a, b) = (*costs.get(&a)?, *costs.get(&b)?);
+                Some(difference(a, b).max_cost(difference(b, a)))
+            })
+            .fold(C::ZERO, C::max_cost)
+    }
+}
+
+/// `a - b`, or zero if `b` is larger.
+fn difference<C: Cost>(a: C, b: C) -> C {
+    if a > b {
+        a - b
+    } else {
+        C::ZERO
+    }
+}
+
+/// A [PathMap] that wraps another [PathMap] and improves its distance heuristic
+/// using [Landmarks].
+///
+/// The wrapped map's moves must cost the same in both directions, so it should
+/// not contain one way edges or links. Otherwise use [Landmarks::estimate] with
+/// [Pathfinder::astar_fn].
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::{landmarks::*, *};
+/// let mut map = PathMap2d::new([20, 20]);
+/// for y in 0..19 {
+///     map.add_obstacle([10, y]);
+/// }
+/// let landmarks = Landmarks::farthest(&map, [0, 0], 8);
+/// let alt = LandmarkPathMap::new(&map, &landmarks);
+/// let mut pf = Pathfinder::new();
+/// let path = pf.astar(&alt, [9, 0], [11, 0]).unwrap();
+/// assert_eq!([11, 0], path.last().unwrap().to_array());
+/// ```
+pub struct LandmarkPathMap<'a, M: PathMap> {
+    map: &'a M,
+    landmarks: &'a Landmarks<M::Cost>,
+}
+
+impl<'a, M: PathMap> LandmarkPathMap<'a, M> {
+    pub fn new(map: &'a M, landmarks: &'a Landmarks<M::Cost>) -> Self {
+        Self { map, landmarks }
+    }
+}
+
+impl<M: PathMap> PathMap for LandmarkPathMap<'_, M> {
+    type Cost = M::Cost;
+    type ExitIterator = M::ExitIterator;
+
+    fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+        self.map.exits(p)
+    }
+
+    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> M::Cost {
+        self.map.cost(a, b)
+    }
+
+    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> M::Cost {
+        let (a, b) = (a.into(), b.into());
+        self.map
+            .distance(a, b)
+            .max_cost(self.landmarks.symmetric_estimate(a, b))
+    }
+
+    fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+        self.map.is_obstacle(p)
+    }
+
+    fn cost_at(&self, a: impl Into<IVec2>, b: impl Into<IVec2>, t: u32) -> Option<M::Cost> {
+        self.map.cost_at(a, b, t)
+    }
+
+    fn exits_with_cost(&self, p: impl Into<IVec2>) -> impl Iterator<Item = (IVec2, M::Cost)> {
+        self.map.exits_with_cost(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathMap2d;
+
+    /// An open map with a U shaped wall that traps searches heading right.
+    fn trap() -> PathMap2d {
+        let mut map = PathMap2d::new([40, 40]);
+        for i in 5..=30 {
+            map.add_obstacle([30, i]);
+            map.add_obstacle([i, 5]);
+            map.add_obstacle([i, 30]);
+        }
+        map
+    }
+
+    #[test]
+    fn fewer_expansions() {
+        let map = trap();
+        let landmarks = Landmarks::farthest(&map, [0, 0], 8);
+        assert_eq!(8, landmarks.landmarks().len());
+        let alt = LandmarkPathMap::new(&map, &landmarks);
+
+        let mut plain = Pathfinder::new();
+        let mut with_alt = Pathfinder::new();
+        let (start, goal) = (IVec2::new(20, 18), IVec2::new(39, 18));
+        let a = plain.astar(&map, start, goal).unwrap().len();
+        let b = with_alt.astar(&alt, start, goal).unwrap().len();
+        assert_eq!(a, b);
+        assert_eq!(plain.costs()[&goal], with_alt.costs()[&goal]);
+        assert!(with_alt.costs().len() < plain.costs().len());
+    }
+
+    #[test]
+    fn admissible() {
+        let mut map = trap();
+        map.add_link([0, 0], [39, 0], 1);
+        let landmarks = Landmarks::farthest(&map, [0, 0], 3);
+        let mut pf = Pathfinder::new();
+        for start in [[0, 0], [20, 18], [39, 39]] {
+            pf.dijkstra(&map, None::<IVec2>, start);
+            for (p, cost) in pf.costs() {
+                assert!(landmarks.estimate(start, *p) <= *cost);
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! A pathmap made up of multiple floors stacked on top of each other, connected
+//! by stairs, elevators or any other connectors.
+
+use glam::{IVec2, UVec2};
+
+use crate::{
+    cost::Cost,
+    grid::SizedGrid,
+    links::Links,
+    pathmap::{Exits, PathMap, PathMap2d},
+};
+
+/// A [PathMap] made up of multiple [PathMap2d] floors of the same size, where
+/// floors are connected by directed links such as stairs or elevators.
+///
+/// Since a [PathMap] only deals in 2d positions, each floor is stacked on top of
+/// the last in a single tall coordinate space. A position on a floor can be
+/// converted to this "global" position with [LayeredPathMap::to_global], and back
+/// again with [LayeredPathMap::to_local]. All [PathMap] functions work with global
+/// positions.
+///
+/// Movement costs are `i32` by default. Other [Cost] types can be used by
+/// creating the map with [LayeredPathMap::from_floors].
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::*;
+/// let mut map = LayeredPathMap::new([10, 10], 2);
+/// // Stairs between the two floors
+/// map.add_two_way_connector(([9, 9], 0), ([9, 9], 1), 4);
+///
+/// let start = map.to_global([0, 0], 0);
+/// let goal = map.to_global([0, 0], 1);
+/// let mut pf = Pathfinder::new();
+/// let path = pf.astar(&map, start, goal).unwrap();
+/// let (_, layer) = map.to_local(path[10]).unwrap();
+/// assert_eq!(1, layer);
+/// ```
+pub struct LayeredPathMap<C = i32> {
+    floors: Vec<PathMap2d<C>>,
+    connectors: Links<C>,
+    size: UVec2,
+}
+
+impl LayeredPathMap {
+    /// Create a new map with the given number of empty floors.
+    pub fn new(size: impl Into<UVec2>, layers: usize) -> Self {
+        let size = size.into();
+        Self {
+            floors: (0..layers).map(|_| PathMap2d::new(size)).collect(),
+            connectors: Links::default(),
+            size,
+        }
+    }
+}
+
+impl<C: Cost> LayeredPathMap<C> {
+    /// Create a new map from a set of floors. Returns [None] if there are no
+    /// floors or if the floors are not all the same size.
+    pub fn from_floors(floors: Vec<PathMap2d<C>>) -> Option<Self> {
+        let size = floors.first()?.size();
+        if floors.iter().any(|f| f.size() != size) {
+            return None;
+        }
+        Some(Self {
+            floors,
+            connectors: Links::default(),
+            size,
+        })
+    }
+
+    /// The number of floors in the map.
+    pub fn layers(&self) -> usize {
+        self.floors.len()
+    }
+
+    /// A reference to the [PathMap2d] for a floor.
+    pub fn floor(&self, layer: usize) -> &PathMap2d<C> {
+        &self.floors[layer]
+    }
+
+    /// A mutable reference to the [PathMap2d] for a floor.
+    pub fn floor_mut(&mut self, layer: usize) -> &mut PathMap2d<C> {
+        &mut self.floors[layer]
+    }
+
+    /// Add a directed connector from a position on one floor to a position on
+    /// another with the given movement cost.
+    pub fn add_connector(
+        &mut self,
+        from: (impl Into<IVec2>, usize),
+        to: (impl Into<IVec2>, usize),
+        cost: C,
+    ) {
+        let from = self.to_global(from.0, from.1);
+        let to = self.to_global(to.0, to.1);
+        self.connectors.add(from, to, cost);
+    }
+
+    /// Add a connector in both directions between two positions with the given
+    /// movement cost.
+    pub fn add_two_way_connector(
+        &mut self,
+        a: (impl Into<IVec2>, usize),
+        b: (impl Into<IVec2>, usize),
+        cost: C,
+    ) {
+        let a = self.to_global(a.0, a.1);
+        let b = self.to_global(b.0, b.1);
+        self.connectors.add(a, b, cost);
+        self.connectors.add(b, a, cost);
+    }
+
+    /// Remove a directed connector. Returns false if the connector didn't exist.
+    pub fn remove_connector(
+        &mut self,
+        from: (impl Into<IVec2>, usize),
+        to: (impl Into<IVec2>, usize),
+    ) -> bool {
+        let from = self.to_global(from.0, from.1);
+        let to = self.to_global(to.0, to.1);
+        self.connectors.remove(from, to)
+    }
+
+    /// The connectors between floors, in global positions.
+    pub fn connectors(&self) -> &Links<C> {
+        &self.connectors
+    }
+
+    /// Convert a position on a floor to a global position.
+    pub fn to_global(&self, xy: impl Into<IVec2>, layer: usize) -> IVec2 {
+        xy.into() + IVec2::new(0, layer as i32 * self.size.y as i32)
+    }
+
+    /// Convert a global position to a position on a floor and that floor's layer.
+    ///
+    /// Returns [None] if the position is below the bottom floor or above the top
+    /// floor.
+    pub fn to_local(&self, p: impl Into<IVec2>) -> Option<(IVec2, usize)> {
+        let p = p.into();
+        let h = self.size.y as i32;
+        let layer = usize::try_from(p.y.div_euclid(h)).ok()?;
+        (layer < self.floors.len()).then(|| (IVec2::new(p.x, p.y.rem_euclid(h)), layer))
+    }
+
+    /// The heuristic distance between two global positions when no connectors
+    /// are used.
+    fn floor_distance(&self, a: IVec2, b: IVec2) -> C {
+        match (self.to_local(a), self.to_local(b)) {
+            (Some((a, la)), Some((b, lb))) if la == lb => self.floors[la].distance(a, b),
+            _ => C::ZERO,
+        }
+    }
+}
+
+impl<C: Cost> SizedGrid for LayeredPathMap<C> {
+    /// The size of a single floor.
+    fn size(&self) -> UVec2 {
+        self.size
+    }
+}
+
+impl<C: Cost> PathMap for LayeredPathMap<C> {
+    type Cost = C;
+    type ExitIterator = LayeredExits;
+
+    fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+        let p = p.into();
+        let local = self.to_local(p);
+        let floor = local.map(|(local, layer)| self.floors[layer].exits(local));
+        let offset = local.map_or(IVec2::ZERO, |(local, _)| p - local);
+        let connectors: Vec<_> = self
+            .connectors
+            .from(p)
+            .iter()
+            .map(|(to, _)| *to)
+            .filter(|to| !self.is_obstacle(*to))
+            .collect();
+        LayeredExits {
+            floor,
+            offset,
+            connectors: connectors.into_iter(),
+        }
+    }
+
+    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> C {
+        let (a, b) = (a.into(), b.into());
+        if let Some(cost) = self.connectors.cost(a, b) {
+            return cost;
+        }
+        match self.to_local(a) {
+            Some((local, layer)) => self.floors[layer].cost(local, b - (a - local)),
+            None => C::ONE,
+        }
+    }
+
+    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> C {
+        let (a, b) = (a.into(), b.into());
+        let d = self.floor_distance(a, b);
+        let layer = |p| self.to_local(p).map(|(_, layer)| layer);
+        let same_layer = layer(a) == layer(b);
+        match self
+            .connectors
+            .distance_bound(a, b, |a, b| self.floor_distance(a, b))
+        {
+            Some(bound) if same_layer => d.min_cost(bound),
+            Some(bound) => bound,
+            None => d,
+        }
+    }
+
+    fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+        match self.to_local(p) {
+            Some((p, layer)) => {
+                let floor = &self.floors[layer];
+                !floor.contains_point(p) || floor.is_obstacle(p)
+            }
+            None => true,
+        }
+    }
+}
+
+/// An iterator over the exits of a [LayeredPathMap].
+pub struct LayeredExits {
+    floor: Option<Exits>,
+    offset: IVec2,
+    connectors: std::vec::IntoIter<IVec2>,
+}
+
+impl Iterator for LayeredExits {
+    type Item = IVec2;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        self.floor
+            .as_mut()
+            .and_then(|f| f.next())
+            .map(|p| p + offset)
+            .or_else(|| self.connectors.next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pathmap::Adjacency, Pathfinder};
+
+    #[test]
+    fn change_floors() {
+        let ground = PathMap2d::from_string(
+            "
+....#.....
+....#.....
+....#.....
+....#.....
+....#.....
+",
+            '#',
+        )
+        .unwrap();
+        let upstairs = PathMap2d::new([10, 5]);
+        let mut map = LayeredPathMap::from_floors(vec![ground, upstairs]).unwrap();
+
+        let start = map.to_global([0, 0], 0);
+        let goal = map.to_global([9, 0], 0);
+        let mut pf = Pathfinder::new();
+        assert!(pf.astar(&map, start, goal).is_none());
+
+        map.add_two_way_connector(([2, 4], 0), ([2, 4], 1), 2);
+        map.add_two_way_connector(([7, 4], 1), ([7, 4], 0), 2);
+        let path = pf.astar(&map, start, goal).unwrap();
+        let layers: Vec<_> = path.iter().map(|p| map.to_local(*p).unwrap().1).collect();
+        assert!(layers.contains(&1));
+        assert_eq!(0, *layers.last().unwrap());
+        assert_eq!(
+            [9, 0],
+            map.to_local(*path.last().unwrap()).unwrap().0.to_array()
+        );
+
+        // Positions never step off the edge of a floor into the next one
+        for p in path {
+            let (local, _) = map.to_local(*p).unwrap();
+            assert!(map.floor(0).contains_point(local));
+        }
+    }
+
+    #[test]
+    fn out_of_range() {
+        let map = LayeredPathMap::new([10, 10], 2);
+        assert!(map.to_local([0, -1]).is_none());
+        assert!(map.to_local([0, 20]).is_none());
+        assert!(map.is_obstacle([0, -1]));
+        assert!(map.is_obstacle([0, 20]));
+        assert!(!map.is_obstacle([0, 19]));
+
+        // Searches can't walk off the bottom or top of the stack
+        let mut pf = Pathfinder::new();
+        assert!(pf.astar(&map, [0, 0], [0, -1]).is_none());
+        assert!(!map.exits([0, 0]).any(|p| p.y < 0));
+        assert!(!map.exits([0, 19]).any(|p| p.y > 19));
+    }
+
+    #[test]
+    fn distance_is_admissible() {
+        let mut map = LayeredPathMap::new([10, 10], 3);
+        map.add_two_way_connector(([0, 0], 0), ([9, 9], 1), 1);
+        map.add_two_way_connector(([0, 9], 1), ([5, 5], 2), 3);
+
+        let mut astar = Pathfinder::new();
+        let mut dijkstra = Pathfinder::new();
+        let start = map.to_global([4, 4], 0);
+        for goal in [([5, 5], 2), ([1, 1], 1), ([9, 0], 0), ([0, 0], 2)] {
+            let goal = map.to_global(goal.0, goal.1);
+            dijkstra.dijkstra(&map, Some(start), goal);
+            astar.astar(&map, start, goal).unwrap();
+            assert_eq!(dijkstra.costs()[&goal], astar.costs()[&goal]);
+        }
+    }
+
+    #[test]
+    fn float_costs() {
+        let floor = || {
+            PathMap2d::with_adjacency(
+                [10, 10],
+                Adjacency::Octile {
+                    cardinal_cost: 1.0,
+                    diagonal_cost: std::f32::consts::SQRT_2,
+                },
+            )
+        };
+        let mut map = LayeredPathMap::from_floors(vec![floor(), floor()]).unwrap();
+        map.add_two_way_connector(([0, 0], 0), ([0, 0], 1), 2.5);
+
+        let goal = map.to_global([3, 0], 1);
+        let mut pf = Pathfinder::new();
+        pf.astar(&map, [0, 0], goal).unwrap();
+        assert_eq!(5.5, pf.costs()[&goal]);
+    }
+}
@@ -1,12 +1,38 @@
+#[cfg(feature = "rayon")]
+pub mod batch;
 pub mod bit_grid;
+pub mod cbs;
+pub mod clearance;
+pub mod cost;
 pub mod dijkstra_map;
 pub mod float_grid;
+pub mod fn_pathmap;
 pub mod grid;
+pub mod heuristic;
+pub mod landmarks;
+pub mod layered;
+pub mod links;
 pub mod min_heap;
+pub mod overlay;
+pub mod path_cache;
 pub mod pathfinder;
 pub mod pathmap;
+pub mod reservation;
+pub mod state_search;
+pub mod stateful;
 
+pub use cbs::Cbs;
+pub use clearance::{ClearanceMap, ClearancePathMap};
+pub use cost::Cost;
 pub use dijkstra_map::DijkstraMap;
+pub use fn_pathmap::FnPathMap;
+pub use heuristic::Heuristic;
+pub use landmarks::{LandmarkPathMap, Landmarks};
+pub use layered::LayeredPathMap;
 pub use min_heap::MinHeap;
-pub use pathfinder::Pathfinder;
+pub use overlay::{Overlay, OverlayPathMap};
+pub use path_cache::PathCache;
+pub use pathfinder::{Pathfinder, TieBreak};
 pub use pathmap::{PathMap, PathMap2d};
+pub use reservation::ReservationTable;
+pub use stateful::{StatefulPathMap, StatefulPathfinder};
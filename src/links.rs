@@ -0,0 +1,120 @@
+//! Directed links between positions that aren't adjacent, such as portals,
+//! teleporters or stairs.
+
+use ahash::HashMap;
+use glam::IVec2;
+
+use crate::cost::Cost;
+
+/// A set of directed links between positions, each with a movement cost.
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::links::Links;
+/// let mut links = Links::new();
+/// links.add([0, 0], [10, 10], 1);
+/// assert_eq!(Some(1), links.cost([0, 0], [10, 10]));
+/// assert_eq!(None, links.cost([10, 10], [0, 0]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Links<C = i32> {
+    links: HashMap<IVec2, Vec<(IVec2, C)>>,
+}
+
+impl<C> Default for Links<C> {
+    fn default() -> Self {
+        Self {
+            links: HashMap::default(),
+        }
+    }
+}
+
+impl<C: Cost> Links<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a link from one position to another. If the link already exists
+    /// its cost will be replaced.
+    pub fn add(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>, cost: C) {
+        let to = to.into();
+        let links = self.links.entry(from.into()).or_default();
+        match links.iter_mut().find(|(p, _)| *p == to) {
+            Some(link) => link.1 = cost,
+            None => links.push((to, cost)),
+        }
+    }
+
+    /// Remove a link from one position to another. Returns false if the link
+    /// didn't exist.
+    pub fn remove(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>) -> bool {
+        let (from, to) = (from.into(), to.into());
+        let Some(links) = self.links.get_mut(&from) else {
+            return false;
+        };
+        let len = links.len();
+        links.retain(|(p, _)| *p != to);
+        let removed = links.len() != len;
+        if links.is_empty() {
+            self.links.remove(&from);
+        }
+        removed
+    }
+
+    /// Remove all links.
+    pub fn clear(&mut self) {
+        self.links.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// The destinations and costs of all links leaving the given position.
+    pub fn from(&self, p: impl Into<IVec2>) -> &[(IVec2, C)] {
+        self.links.get(&p.into()).map_or(&[], |l| l.as_slice())
+    }
+
+    /// The cost of the link from one position to another, if it exists.
+    pub fn cost(&self, from: impl Into<IVec2>, to: impl Into<IVec2>) -> Option<C> {
+        let to = to.into();
+        self.from(from)
+            .iter()
+            .find(|(p, _)| *p == to)
+            .map(|(_, c)| *c)
+    }
+
+    /// An iterator over every link as `(from, to, cost)`.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, IVec2, C)> + '_ {
+        self.links
+            .iter()
+            .flat_map(|(from, links)| links.iter().map(move |(to, c)| (*from, *to, *c)))
+    }
+
+    /// A lower bound on the cost of any path between `a` and `b`, in either
+    /// direction, that uses at least one link. Returns [None] if there are no links.
+    ///
+    /// `distance` must be a lower bound on the cost between two positions when no
+    /// links are used. The bound is the cheapest way to reach any link entrance,
+    /// plus the cheapest link, plus the cheapest way from any link exit to the
+    /// destination, so it remains admissible when a path uses several links.
+    pub fn distance_bound(
+        &self,
+        a: IVec2,
+        b: IVec2,
+        distance: impl Fn(IVec2, IVec2) -> C,
+    ) -> Option<C> {
+        let mut links = self.iter();
+        let (from, to, mut cost) = links.next()?;
+        let (mut entry_a, mut entry_b) = (distance(a, from), distance(b, from));
+        let (mut exit_a, mut exit_b) = (distance(to, a), distance(to, b));
+        for (from, to, c) in links {
+            cost = cost.min_cost(c);
+            entry_a = entry_a.min_cost(distance(a, from));
+            entry_b = entry_b.min_cost(distance(b, from));
+            exit_a = exit_a.min_cost(distance(to, a));
+            exit_b = exit_b.min_cost(distance(to, b));
+        }
+        Some(cost + (entry_a + exit_b).min_cost(entry_b + exit_a))
+    }
+}
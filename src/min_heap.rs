@@ -4,7 +4,10 @@ use glam::IVec2;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 
-/// A min heap for pathfinding that can store positions with a cost.
+use crate::cost::Cost;
+
+/// A min heap for pathfinding that can store positions with a cost of any
+/// [Cost] type.
 ///
 /// # Example
 /// ```rust
@@ -17,31 +20,70 @@ use std::collections::BinaryHeap;
 /// heap.push([7,7], 1);
 /// assert_eq!(heap.pop().unwrap().to_array(), [15,15]);
 /// ```
-#[derive(Debug, Default, Clone)]
-pub struct MinHeap {
-    heap: BinaryHeap<Cell>,
+///
+/// Cells with equal costs are ordered by their tie breaking value, then by
+/// insertion order if [MinHeap::with_insertion_order] is set, then by y and x.
+/// This is a total order, so the same pushes always pop in the same order.
+#[derive(Debug, Clone)]
+pub struct MinHeap<C = i32> {
+    heap: BinaryHeap<Cell<C>>,
+    insertion_order: bool,
+    next_seq: u64,
+}
+
+impl<C: Cost> Default for MinHeap<C> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl MinHeap {
+impl<C: Cost> MinHeap<C> {
     pub fn new() -> Self {
-        Self {
-            heap: BinaryHeap::new(),
-        }
+        Self::with_capacity(0)
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             heap: BinaryHeap::with_capacity(capacity),
+            insertion_order: false,
+            next_seq: 0,
         }
     }
 
+    /// Break ties between equal costs by popping the earliest pushed cell
+    /// first, before comparing positions.
+    pub fn with_insertion_order(mut self, insertion_order: bool) -> Self {
+        self.insertion_order = insertion_order;
+        self
+    }
+
+    /// Set whether ties between equal costs are broken by insertion order.
+    pub fn set_insertion_order(&mut self, insertion_order: bool) {
+        self.insertion_order = insertion_order;
+    }
+
     pub fn clear(&mut self) {
         self.heap.clear();
+        self.next_seq = 0;
+    }
+
+    pub fn push(&mut self, xy: impl Into<IVec2>, cost: C) {
+        self.push_with_tie(xy, cost, C::ZERO);
     }
 
-    pub fn push(&mut self, xy: impl Into<IVec2>, cost: i32) {
+    /// Push a position with a secondary `tie` value. Between equal costs the
+    /// lowest `tie` is popped first.
+    pub fn push_with_tie(&mut self, xy: impl Into<IVec2>, cost: C, tie: C) {
+        let seq = if self.insertion_order {
+            self.next_seq += 1;
+            self.next_seq
+        } else {
+            0
+        };
         self.heap.push(Cell {
             cost,
+            tie,
+            seq,
             pos: xy.into(),
         });
     }
@@ -60,25 +102,40 @@ impl MinHeap {
 }
 
 /// A cell for our min heap.
-#[derive(Eq, PartialEq, Debug, Default, Clone, Copy)]
-struct Cell {
-    cost: i32,
+#[derive(Debug, Default, Clone, Copy)]
+struct Cell<C> {
+    cost: C,
+    tie: C,
+    seq: u64,
     pos: IVec2,
 }
 
-impl Ord for Cell {
+impl<C: Cost> PartialEq for Cell<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C: Cost> Eq for Cell<C> {}
+
+impl<C: Cost> Ord for Cell<C> {
     fn cmp(&self, other: &Self) -> Ordering {
-        // order by cost, then y, then x
-        other.cost.cmp(&self.cost).then_with(|| {
-            self.pos
-                .y
-                .cmp(&other.pos.y)
-                .then_with(|| self.pos.x.cmp(&other.pos.x))
-        })
+        // order by cost, then tie, then sequence, then y, then x
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.tie.total_cmp(&self.tie))
+            .then_with(|| other.seq.cmp(&self.seq))
+            .then_with(|| {
+                self.pos
+                    .y
+                    .cmp(&other.pos.y)
+                    .then_with(|| self.pos.x.cmp(&other.pos.x))
+            })
     }
 }
 
-impl PartialOrd for Cell {
+impl<C: Cost> PartialOrd for Cell<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
@@ -101,4 +158,34 @@ mod tests {
         assert_eq!([2, 2], heap.pop().unwrap().to_array());
         assert_eq!([5, 5], heap.pop().unwrap().to_array());
     }
+
+    #[test]
+    fn float_heap() {
+        let mut heap = MinHeap::new();
+        heap.push([2, 2], 1.5);
+        heap.push([1, 1], std::f32::consts::SQRT_2);
+        heap.push([3, 3], -0.5);
+
+        assert_eq!([3, 3], heap.pop().unwrap().to_array());
+        assert_eq!([1, 1], heap.pop().unwrap().to_array());
+        assert_eq!([2, 2], heap.pop().unwrap().to_array());
+    }
+
+    #[test]
+    fn ties() {
+        let mut heap = MinHeap::new();
+        heap.push_with_tie([0, 0], 1, 5);
+        heap.push_with_tie([1, 1], 1, 2);
+        heap.push([9, 9], 2);
+        assert_eq!([1, 1], heap.pop().unwrap().to_array());
+        assert_eq!([0, 0], heap.pop().unwrap().to_array());
+
+        let mut heap = MinHeap::new().with_insertion_order(true);
+        heap.push([0, 0], 1);
+        heap.push([5, 5], 1);
+        heap.push([1, 1], 1);
+        assert_eq!([0, 0], heap.pop().unwrap().to_array());
+        assert_eq!([5, 5], heap.pop().unwrap().to_array());
+        assert_eq!([1, 1], heap.pop().unwrap().to_array());
+    }
 }
@@ -0,0 +1,215 @@
+//! Temporary obstacles and extra costs layered on top of a shared [PathMap].
+
+use ahash::HashMap;
+use glam::{IVec2, UVec2};
+
+use crate::{bit_grid::BitGrid, cost::Cost, grid::SizedGrid, pathmap::PathMap};
+
+/// A set of blocked positions and extra movement costs which can be layered on
+/// top of any [PathMap] with an [OverlayPathMap], without changing that map.
+///
+/// Useful for avoiding occupied tiles or dangerous areas for a single agent or
+/// query. An overlay can be cleared and re-used to avoid allocations.
+///
+/// Extra costs are added to the cost of moving onto a position and must not be
+/// negative, so the wrapped map's distance heuristic stays admissible.
+#[derive(Debug, Clone)]
+pub struct Overlay<C = i32> {
+    blocked: BitGrid,
+    costs: HashMap<IVec2, C>,
+}
+
+impl<C> Default for Overlay<C> {
+    fn default() -> Self {
+        Self {
+            blocked: BitGrid::default(),
+            costs: HashMap::default(),
+        }
+    }
+}
+
+impl<C: Cost> Overlay<C> {
+    /// Create an empty overlay covering positions from `[0, 0]` up to `size`.
+    /// Positions outside that area can't be blocked.
+    pub fn new(size: impl Into<UVec2>) -> Self {
+        Self {
+            blocked: BitGrid::new(size),
+            costs: HashMap::default(),
+        }
+    }
+
+    /// Block a position for any path using the overlay.
+    pub fn block(&mut self, p: impl Into<IVec2>) {
+        self.set_blocked(p, true);
+    }
+
+    pub fn unblock(&mut self, p: impl Into<IVec2>) {
+        self.set_blocked(p, false);
+    }
+
+    /// Set whether a position is blocked. Positions outside the overlay are ignored.
+    pub fn set_blocked(&mut self, p: impl Into<IVec2>, v: bool) {
+        if let Some(i) = self.blocked.try_xy_to_index(p) {
+            self.blocked.set_index(i, v);
+        }
+    }
+
+    /// Whether a position has been blocked by the overlay.
+    pub fn is_blocked(&self, p: impl Into<IVec2>) -> bool {
+        self.blocked
+            .try_xy_to_index(p)
+            .is_some_and(|i| self.blocked.get_index(i))
+    }
+
+    /// Add an extra cost for moving onto a position, on top of any extra cost
+    /// already added.
+    pub fn add_cost(&mut self, p: impl Into<IVec2>, cost: C) {
+        let extra = self.costs.entry(p.into()).or_insert(C::ZERO);
+        *extra = *extra + cost;
+    }
+
+    /// The extra cost for moving onto a position.
+    pub fn extra_cost(&self, p: impl Into<IVec2>) -> C {
+        self.costs.get(&p.into()).copied().unwrap_or(C::ZERO)
+    }
+
+    /// A reference to the underlying bit grid of blocked positions.
+    pub fn blocked_grid(&self) -> &BitGrid {
+        &self.blocked
+    }
+
+    /// A mutable reference to the underlying bit grid of blocked positions.
+    pub fn blocked_grid_mut(&mut self) -> &mut BitGrid {
+        &mut self.blocked
+    }
+
+    /// Remove all blocked positions and extra costs.
+    pub fn clear(&mut self) {
+        self.blocked.set_all(false);
+        self.costs.clear();
+    }
+}
+
+impl<C> SizedGrid for Overlay<C> {
+    fn size(&self) -> UVec2 {
+        self.blocked.size()
+    }
+}
+
+/// A [PathMap] that wraps another [PathMap] and applies the blocked positions
+/// and extra costs from an [Overlay].
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::{overlay::*, *};
+/// let map = PathMap2d::new([10, 10]);
+/// let mut overlay = Overlay::new([10, 10]);
+/// for y in 0..9 {
+///     overlay.block([5, y]);
+/// }
+/// let mut pf = Pathfinder::new();
+/// assert_eq!(10, pf.astar(&map, [0, 0], [9, 0]).unwrap().len());
+/// let overlaid = OverlayPathMap::new(&map, &overlay);
+/// assert!(pf.astar(&overlaid, [0, 0], [9, 0]).unwrap().len() > 10);
+/// ```
+pub struct OverlayPathMap<'a, M: PathMap> {
+    map: &'a M,
+    overlay: &'a Overlay<M::Cost>,
+}
+
+impl<'a, M: PathMap> OverlayPathMap<'a, M> {
+    pub fn new(map: &'a M, overlay: &'a Overlay<M::Cost>) -> Self {
+        Self { map, overlay }
+    }
+}
+
+impl<'a, M: PathMap> PathMap for OverlayPathMap<'a, M> {
+    type Cost = M::Cost;
+    type ExitIterator = OverlayExits<'a, M::ExitIterator, M::Cost>;
+
+    fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+        OverlayExits {
+            exits: self.map.exits(p),
+            overlay: self.overlay,
+        }
+    }
+
+    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> M::Cost {
+        let b = b.into();
+        self.map.cost(a, b) + self.overlay.extra_cost(b)
+    }
+
+    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> M::Cost {
+        self.map.distance(a, b)
+    }
+
+    fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+        let p = p.into();
+        self.overlay.is_blocked(p) || self.map.is_obstacle(p)
+    }
+
+    fn cost_at(&self, a: impl Into<IVec2>, b: impl Into<IVec2>, t: u32) -> Option<M::Cost> {
+        let b = b.into();
+        Some(self.map.cost_at(a, b, t)? + self.overlay.extra_cost(b))
+    }
+
+    fn exits_with_cost(&self, p: impl Into<IVec2>) -> impl Iterator<Item = (IVec2, M::Cost)> {
+        let overlay = self.overlay;
+        self.map
+            .exits_with_cost(p)
+            .filter(move |(p, _)| !overlay.is_blocked(*p))
+            .map(move |(p, cost)| (p, cost + overlay.extra_cost(p)))
+    }
+}
+
+/// An iterator over the exits of an [OverlayPathMap].
+pub struct OverlayExits<'a, I, C> {
+    exits: I,
+    overlay: &'a Overlay<C>,
+}
+
+impl<I: Iterator<Item = IVec2>, C: Cost> Iterator for OverlayExits<'_, I, C> {
+    type Item = IVec2;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let overlay = self.overlay;
+        self.exits.find(|p| !overlay.is_blocked(*p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PathMap2d, Pathfinder};
+
+    #[test]
+    fn blocked_and_costs() {
+        let mut map = PathMap2d::new([10, 3]);
+        map.add_obstacle([5, 0]);
+        let mut overlay = Overlay::new([10, 3]);
+        let mut pf = Pathfinder::new();
+
+        // Another agent standing in the way
+        overlay.block([5, 1]);
+        let path = pf
+            .astar(&OverlayPathMap::new(&map, &overlay), [0, 1], [9, 1])
+            .unwrap();
+        assert!(path.contains(&IVec2::new(5, 2)));
+        assert!(!map.is_obstacle([5, 1]));
+
+        // A dangerous area that costs more but is the only way through
+        overlay.clear();
+        overlay.block([5, 1]);
+        overlay.add_cost([5, 2], 1);
+        let overlaid = OverlayPathMap::new(&map, &overlay);
+        assert!(overlaid.is_obstacle([5, 0]));
+        assert!(overlaid.is_obstacle([5, 1]));
+        assert_eq!(overlaid.cost([4, 2], [5, 2]), map.cost([4, 2], [5, 2]) + 1);
+        assert!(pf.astar(&overlaid, [0, 1], [9, 1]).is_some());
+
+        overlay.block([5, 2]);
+        let overlaid = OverlayPathMap::new(&map, &overlay);
+        assert!(pf.astar(&overlaid, [0, 1], [9, 1]).is_none());
+        assert!(pf.astar(&map, [0, 1], [9, 1]).is_some());
+    }
+}
@@ -0,0 +1,219 @@
+//! A cache of previously found paths which is invalidated as the map changes.
+
+use ahash::{HashMap, HashSet};
+use glam::IVec2;
+
+use crate::{cost::Cost, pathfinder::Pathfinder, pathmap::PathMap2d};
+
+/// Stores paths found by a [Pathfinder] so the same path doesn't need to be
+/// found repeatedly.
+///
+/// The cache tracks which positions each cached path crosses. Before each
+/// lookup it checks the map's [changes](PathMap2d::changes_since) and removes
+/// any cached paths that cross a changed position, so obstacles, links and
+/// blocked edges can be changed on the map directly. A cache should only be
+/// used with a single map.
+///
+/// Note that removing an obstacle only invalidates paths that cross it, so a
+/// cached path may no longer be the shortest one after obstacles are removed.
+/// Failed searches are not cached.
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::*;
+/// let mut map = PathMap2d::new([10, 10]);
+/// let mut cache = PathCache::new();
+/// assert!(cache.astar(&map, [0, 0], [9, 0]).is_some());
+/// assert!(cache.get(&map, [0, 0], [9, 0]).is_some());
+///
+/// map.add_obstacle([5, 0]);
+/// assert!(cache.get(&map, [0, 0], [9, 0]).is_none());
+/// ```
+pub struct PathCache<C = i32> {
+    pathfinder: Pathfinder<C>,
+    paths: HashMap<(IVec2, IVec2), Vec<IVec2>>,
+    crossed_by: HashMap<IVec2, HashSet<(IVec2, IVec2)>>,
+    /// The [PathMap2d::generation] of the map when the cache was last synced.
+    generation: u64,
+}
+
+impl<C: Cost> Default for PathCache<C> {
+    fn default() -> Self {
+        Self {
+            pathfinder: Pathfinder::default(),
+            paths: HashMap::default(),
+            crossed_by: HashMap::default(),
+            generation: 0,
+        }
+    }
+}
+
+impl<C: Cost> PathCache<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Find a path using [Pathfinder::astar], or return the cached path if
+    /// one exists.
+    pub fn astar(
+        &mut self,
+        map: &PathMap2d<C>,
+        start: impl Into<IVec2>,
+        goal: impl Into<IVec2>,
+    ) -> Option<&[IVec2]> {
+        self.sync(map);
+        let key = (start.into(), goal.into());
+        if !self.paths.contains_key(&key) {
+            let path = self.pathfinder.astar(map, key.0, key.1)?.to_vec();
+            self.insert(key.0, key.1, path);
+        }
+        self.paths.get(&key).map(|p| p.as_slice())
+    }
+
+    /// The cached path between two positions, if there is one.
+    pub fn get(
+        &mut self,
+        map: &PathMap2d<C>,
+        start: impl Into<IVec2>,
+        goal: impl Into<IVec2>,
+    ) -> Option<&[IVec2]> {
+        self.sync(map);
+        self.paths
+            .get(&(start.into(), goal.into()))
+            .map(|p| p.as_slice())
+    }
+
+    /// Remove any cached paths crossing positions that have changed on the map
+    /// since the last sync. This is done automatically by [PathCache::astar]
+    /// and [PathCache::get].
+    pub fn sync(&mut self, map: &PathMap2d<C>) {
+        match map.changes_since(self.generation) {
+            Some(changes) => {
+                for p in changes {
+                    self.invalidate(*p);
+                }
+            }
+            None => self.clear(),
+        }
+        self.generation = map.generation();
+    }
+
+    /// Add a path between two positions to the cache, replacing any existing path.
+    pub fn insert(&mut self, start: impl Into<IVec2>, goal: impl Into<IVec2>, path: Vec<IVec2>) {
+        let key = (start.into(), goal.into());
+        self.remove(key.0, key.1);
+        for p in &path {
+            self.crossed_by.entry(*p).or_default().insert(key);
+        }
+        self.paths.insert(key, path);
+    }
+
+    /// Remove the cached path between two positions. Returns false if there
+    /// was no cached path.
+    pub fn remove(&mut self, start: impl Into<IVec2>, goal: impl Into<IVec2>) -> bool {
+        let key = (start.into(), goal.into());
+        let Some(path) = self.paths.remove(&key) else {
+            return false;
+        };
+        for p in path {
+            if let Some(keys) = self.crossed_by.get_mut(&p) {
+                keys.remove(&key);
+                if keys.is_empty() {
+                    self.crossed_by.remove(&p);
+                }
+            }
+        }
+        true
+    }
+
+    /// Remove every cached path that crosses the given position.
+    pub fn invalidate(&mut self, p: impl Into<IVec2>) {
+        let Some(keys) = self.crossed_by.remove(&p.into()) else {
+            return;
+        };
+        for (start, goal) in keys {
+            self.remove(start, goal);
+        }
+    }
+
+    /// The number of cached paths.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Remove all cached paths.
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.crossed_by.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalidation() {
+        let mut map = PathMap2d::new([10, 10]);
+        let mut cache = PathCache::new();
+        let a = cache.astar(&map, [0, 0], [9, 0]).unwrap().to_vec();
+        cache.astar(&map, [0, 9], [9, 9]).unwrap();
+        assert_eq!(2, cache.len());
+
+        // Cached results are returned without searching again
+        assert_eq!(a, cache.astar(&map, [0, 0], [9, 0]).unwrap());
+
+        // Changes away from any path don't invalidate anything
+        map.toggle_obstacle([5, 5]);
+        cache.sync(&map);
+        assert_eq!(2, cache.len());
+
+        map.move_obstacle([5, 5], [5, 0]);
+        cache.sync(&map);
+        assert_eq!(1, cache.len());
+        assert!(cache.get(&map, [0, 0], [9, 0]).is_none());
+        assert!(cache.get(&map, [0, 9], [9, 9]).is_some());
+
+        let b = cache.astar(&map, [0, 0], [9, 0]).unwrap();
+        assert!(!b.contains(&IVec2::new(5, 0)));
+
+        map.set_obstacle([5, 0], false);
+        cache.invalidate([9, 9]);
+        assert!(cache.get(&map, [0, 9], [9, 9]).is_none());
+        assert_eq!(1, cache.len());
+        assert!(cache.crossed_by.values().all(|k| k.len() == 1));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn direct_map_changes() {
+        let mut map = PathMap2d::new([10, 10]);
+        let mut cache = PathCache::new();
+        cache.astar(&map, [0, 0], [9, 0]).unwrap();
+        cache.astar(&map, [0, 9], [9, 9]).unwrap();
+        cache.astar(&map, [0, 5], [9, 5]).unwrap();
+
+        map.set_obstacle([5, 0], true);
+        assert!(cache.get(&map, [0, 0], [9, 0]).is_none());
+        let path = cache.astar(&map, [0, 0], [9, 0]).unwrap();
+        assert!(!path.contains(&IVec2::new(5, 0)));
+
+        map.add_wall([4, 9], [5, 9]);
+        assert!(cache.get(&map, [0, 9], [9, 9]).is_none());
+
+        map.add_link([9, 5], [0, 0], 1);
+        assert!(cache.get(&map, [0, 5], [9, 5]).is_none());
+
+        // Changes that can't be tracked clear the whole cache
+        cache.astar(&map, [0, 5], [9, 5]).unwrap();
+        map.obstacle_grid_mut();
+        assert!(cache.get(&map, [0, 5], [9, 5]).is_none());
+        assert!(cache.is_empty());
+    }
+}
@@ -2,13 +2,47 @@
 
 use ahash::{HashMap, HashMapExt};
 use glam::IVec2;
-use std::collections::hash_map::Entry;
+use std::collections::{hash_map::Entry, VecDeque};
 
-use crate::{min_heap::MinHeap, pathmap::PathMap};
+use crate::{
+    cost::Cost, heuristic::Heuristic, min_heap::MinHeap, pathmap::PathMap,
+    reservation::ReservationTable, state_search::StateSearch,
+};
+
+/// How [Pathfinder] chooses between positions with the same estimated total
+/// cost.
+///
+/// Whichever strategy is used the search frontier has a total order, so a
+/// search on the same map always expands positions in the same order. With
+/// integer costs this holds across platforms, which makes paths safe to use
+/// in lockstep multiplayer.
+///
+/// Only the searches over plain positions use the tie break. The searches
+/// over other states, [Pathfinder::astar_timed], [Pathfinder::astar_reserved],
+/// [Pathfinder::astar_turning], [StatefulPathfinder](crate::stateful::StatefulPathfinder)
+/// and [Cbs](crate::cbs::Cbs), ignore it and always prefer the state that was
+/// added to the frontier first, like [TieBreak::Fifo].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TieBreak {
+    /// Prefer the position with the highest y, then the highest x.
+    #[default]
+    Position,
+    /// Prefer the position with the lowest heuristic estimate, which is the
+    /// same as preferring the highest cost so far. Tends to expand fewer
+    /// positions on open maps.
+    LowerH,
+    /// Prefer the position that was added to the frontier first.
+    Fifo,
+    /// Prefer the position closest to the straight line from the start to the
+    /// goal, which gives straighter looking paths. Only used by searches with a
+    /// single goal, others fall back to [TieBreak::Position].
+    CrossProduct,
+}
 
 /// Utility for pathfinding that supports several simple algorithms.
 ///
-/// Maintains internal state so it can be re-used to avoid allocations.
+/// Maintains internal state so it can be re-used to avoid allocations. The
+/// [Cost] type `C` must match the [PathMap::Cost] of any maps it's used with.
 ///
 /// # Example
 ///
@@ -21,15 +55,26 @@ use crate::{min_heap::MinHeap, pathmap::PathMap};
 /// let path = pathfinder.astar(&pathmap, [0, 0], [5, 0]).unwrap();
 /// assert_eq!(6, path.len());
 /// ```
-#[derive(Default)]
-pub struct Pathfinder {
-    frontier: MinHeap,
+pub struct Pathfinder<C = i32> {
+    frontier: MinHeap<C>,
+    queue: VecDeque<IVec2>,
     came_from: HashMap<IVec2, IVec2>,
-    costs: HashMap<IVec2, i32>,
+    costs: HashMap<IVec2, C>,
+    sources: HashMap<IVec2, IVec2>,
+    timed: StateSearch<(IVec2, u32), C>,
+    headings: StateSearch<(IVec2, IVec2), C>,
     path: Vec<IVec2>,
+    arrivals: Vec<u32>,
+    tie_break: TieBreak,
+}
+
+impl<C: Cost> Default for Pathfinder<C> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
 }
 
-impl Pathfinder {
+impl<C: Cost> Pathfinder<C> {
     pub fn new() -> Self {
         Self::default()
     }
@@ -39,9 +84,56 @@ impl Pathfinder {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             frontier: MinHeap::with_capacity(capacity),
+            queue: VecDeque::with_capacity(capacity),
             came_from: HashMap::with_capacity(capacity),
             costs: HashMap::with_capacity(capacity),
+            sources: HashMap::new(),
+            timed: StateSearch::new(),
+            headings: StateSearch::new(),
             path: Vec::with_capacity(capacity / 4),
+            arrivals: Vec::new(),
+            tie_break: TieBreak::default(),
+        }
+    }
+
+    /// Set how the pathfinder chooses between positions with equal costs.
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let map = PathMap2d::new([10, 10]);
+    /// let mut pf = Pathfinder::new().with_tie_break(TieBreak::CrossProduct);
+    /// let path = pf.astar(&map, [0, 0], [6, 3]).unwrap();
+    /// assert_eq!(7, path.len());
+    /// ```
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.set_tie_break(tie_break);
+        self
+    }
+
+    /// Set how the pathfinder chooses between positions with equal costs.
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+        self.frontier
+            .set_insertion_order(tie_break == TieBreak::Fifo);
+    }
+
+    /// How the pathfinder chooses between positions with equal costs.
+    pub fn tie_break(&self) -> TieBreak {
+        self.tie_break
+    }
+
+    /// The secondary frontier value for a position with the heuristic estimate
+    /// `h`, for searches along the line from `start` to `goal` if there is one.
+    fn tie(&self, p: IVec2, h: C, line: Option<(IVec2, IVec2)>) -> C {
+        match (self.tie_break, line) {
+            (TieBreak::LowerH, _) => h,
+            (TieBreak::CrossProduct, Some((start, goal))) => {
+                let (a, b) = ((p - goal).as_i64vec2(), (start - goal).as_i64vec2());
+                let cross = (a.x * b.y - b.x * a.y).abs();
+                C::from_i32(cross.min(i32::MAX as i64) as i32)
+            }
+            _ => C::ZERO,
         }
     }
 
@@ -63,27 +155,32 @@ impl Pathfinder {
     /// assert_eq!(6, path.len());
     pub fn astar(
         &mut self,
-        map: &impl PathMap,
+        map: &impl PathMap<Cost = C>,
         start: impl Into<IVec2>,
         goal: impl Into<IVec2>,
     ) -> Option<&[IVec2]> {
         self.clear();
         let start = start.into();
         let goal = goal.into();
-        self.frontier.push(start, 0);
-        self.costs.insert(start, 0);
+        if start == goal {
+            self.path.push(start);
+            return Some(self.path.as_slice());
+        }
+        self.frontier.push(start, C::ZERO);
+        self.costs.insert(start, C::ZERO);
 
         while let Some(curr) = self.frontier.pop() {
             if curr == goal {
                 break;
             }
 
-            for next in map.exits(curr) {
-                let new_cost = self.costs[&curr] + map.cost(curr, next);
+            for (next, cost) in map.exits_with_cost(curr) {
+                let new_cost = self.costs[&curr] + cost;
                 if !self.costs.contains_key(&next) || new_cost < self.costs[&next] {
                     self.costs.insert(next, new_cost);
-                    self.frontier
-                        .push(next, new_cost + map.distance(goal, next));
+                    let h = map.distance(goal, next);
+                    let tie = self.tie(next, h, Some((start, goal)));
+                    self.frontier.push_with_tie(next, new_cost + h, tie);
                     self.came_from.insert(next, curr);
                 }
             }
@@ -91,6 +188,397 @@ impl Pathfinder {
         self.build_path(start, goal)
     }
 
+    /// Find a path to a goal using the [A*] algorithm with the given [Heuristic]
+    /// in place of [PathMap::distance].
+    ///
+    /// Returns a slice of points representing the path, or [None] if no path
+    /// can be found.
+    ///
+    /// [A*]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#astar
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::{heuristic::Euclidean, *};
+    /// let mut pf = Pathfinder::new();
+    /// let map = PathMap2d::new([10, 10]);
+    /// let path = pf
+    ///     .astar_with_heuristic(&map, [0, 0], [5, 3], &Euclidean { cost: 2.0 })
+    ///     .unwrap();
+    /// assert_eq!(6, path.len());
+    /// ```
+    pub fn astar_with_heuristic(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: impl Into<IVec2>,
+        goal: impl Into<IVec2>,
+        heuristic: &(impl Heuristic<C> + ?Sized),
+    ) -> Option<&[IVec2]> {
+        let (start, goal) = (start.into(), goal.into());
+        let heuristic = |p: IVec2| heuristic.estimate(p, goal);
+        self.search(
+            map,
+            start,
+            |p| p == goal,
+            Some(&heuristic),
+            Some((start, goal)),
+        )
+    }
+
+    /// Find a path to whichever of the given goals is the cheapest to reach
+    /// using the [A*] algorithm.
+    ///
+    /// Returns a slice of points representing the path, or [None] if none of
+    /// the goals can be reached. The goal that was reached is the last point
+    /// in the path.
+    ///
+    /// [A*]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#astar
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// use glam::IVec2;
+    /// let mut pf = Pathfinder::new();
+    /// let map = PathMap2d::new([10, 10]);
+    /// let goals = [IVec2::new(9, 9), IVec2::new(0, 3)];
+    /// let path = pf.astar_multi(&map, [0, 0], &goals).unwrap();
+    /// assert_eq!([0, 3], path.last().unwrap().to_array());
+    /// ```
+    pub fn astar_multi(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: impl Into<IVec2>,
+        goals: &[IVec2],
+    ) -> Option<&[IVec2]> {
+        let heuristic = |p: IVec2| {
+            goals
+                .iter()
+                .map(|g| map.distance(*g, p))
+                .reduce(C::min_cost)
+                .unwrap_or(C::ZERO)
+        };
+        self.astar_fn(map, start, |p| goals.contains(&p), Some(&heuristic))
+    }
+
+    /// Find a path to the cheapest position that satisfies `goal_fn` using
+    /// the [A*] algorithm.
+    ///
+    /// An optional `heuristic` can be provided which should estimate the
+    /// remaining cost from a position to the nearest goal. For the resulting
+    /// path to be the cheapest it must never overestimate that cost. If no
+    /// heuristic is provided the search behaves like [Pathfinder::dijkstra].
+    ///
+    /// Returns a slice of points representing the path, or [None] if no goal
+    /// can be reached.
+    ///
+    /// [A*]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#astar
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let mut pf = Pathfinder::new();
+    /// let map = PathMap2d::new([10, 10]);
+    /// let path = pf.astar_fn(&map, [0, 0], |p| p.x == 4, None).unwrap();
+    /// assert_eq!(5, path.len());
+    /// ```
+    pub fn astar_fn(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: impl Into<IVec2>,
+        goal_fn: impl Fn(IVec2) -> bool,
+        heuristic: Option<&dyn Fn(IVec2) -> C>,
+    ) -> Option<&[IVec2]> {
+        self.search(map, start.into(), goal_fn, heuristic, None)
+    }
+
+    /// The search behind [Pathfinder::astar_fn], with the line from the start
+    /// to a single goal used by [TieBreak::CrossProduct].
+    fn search(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: IVec2,
+        goal_fn: impl Fn(IVec2) -> bool,
+        heuristic: Option<&dyn Fn(IVec2) -> C>,
+        line: Option<(IVec2, IVec2)>,
+    ) -> Option<&[IVec2]> {
+        self.clear();
+        if goal_fn(start) {
+            self.path.push(start);
+            return Some(self.path.as_slice());
+        }
+        self.frontier.push(start, C::ZERO);
+        self.costs.insert(start, C::ZERO);
+
+        let mut goal = None;
+        while let Some(curr) = self.frontier.pop() {
+            if goal_fn(curr) {
+                goal = Some(curr);
+                break;
+            }
+
+            for (next, cost) in map.exits_with_cost(curr) {
+                let new_cost = self.costs[&curr] + cost;
+                if !self.costs.contains_key(&next) || new_cost < self.costs[&next] {
+                    self.costs.insert(next, new_cost);
+                    let h = heuristic.map_or(C::ZERO, |h| h(next));
+                    let tie = self.tie(next, h, line);
+                    self.frontier.push_with_tie(next, new_cost + h, tie);
+                    self.came_from.insert(next, curr);
+                }
+            }
+        }
+        self.build_path(start, goal?)
+    }
+
+    /// Check that a path can still be followed on a map.
+    ///
+    /// Returns the index of the first point in the path that is an obstacle or
+    /// can't be reached from the point before it, or [None] if the whole path
+    /// is valid. The first point of the path is only checked for obstacles. A
+    /// point repeated to wait in place, as in paths from [Pathfinder::astar_timed]
+    /// or [Pathfinder::astar_reserved], is a valid step.
+    pub fn validate(&self, map: &impl PathMap<Cost = C>, path: &[IVec2]) -> Option<usize> {
+        if map.is_obstacle(*path.first()?) {
+            return Some(0);
+        }
+        path.windows(2)
+            .position(|w| !Self::is_step(map, w[0], w[1]))
+            .map(|i| i + 1)
+    }
+
+    /// Whether `b` can be reached from `a` in a single step, or by waiting.
+    fn is_step(map: &impl PathMap<Cost = C>, a: IVec2, b: IVec2) -> bool {
+        !map.is_obstacle(b) && (a == b || map.exits(a).any(|p| p == b))
+    }
+
+    /// Repair a path that may have been broken by changes to the map.
+    ///
+    /// The path is kept up to the point before the first invalid point, as
+    /// returned by [Pathfinder::validate]. From there a detour is found to the
+    /// nearest later point from which the rest of the path is still valid, and
+    /// the detour is spliced into the path. At worst the detour leads to the
+    /// end of the path.
+    ///
+    /// Waits in the kept parts of the path are preserved, but the detour never
+    /// waits, so any timing of the path around the detour is lost.
+    ///
+    /// Returns the repaired path, or [None] if the start or end of the path is
+    /// blocked or no detour can be found.
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let mut pf = Pathfinder::new();
+    /// let mut map = PathMap2d::new([10, 10]);
+    /// let path = pf.astar(&map, [0, 5], [9, 5]).unwrap().to_vec();
+    /// map.add_obstacle([5, 5]);
+    /// assert_eq!(Some(5), pf.validate(&map, &path));
+    /// let repaired = pf.repair(&map, &path).unwrap().to_vec();
+    /// assert_eq!(None, pf.validate(&map, &repaired));
+    /// ```
+    pub fn repair(&mut self, map: &impl PathMap<Cost = C>, path: &[IVec2]) -> Option<&[IVec2]> {
+        let Some(broken) = self.validate(map, path) else {
+            self.path.clear();
+            self.path.extend_from_slice(path);
+            return Some(self.path.as_slice());
+        };
+        if broken == 0 {
+            return None;
+        }
+        let from = path[broken - 1];
+        if map.is_obstacle(*path.last()?) {
+            return None;
+        }
+
+        // The earliest index from which the rest of the path is valid
+        let mut valid_from = path.len() - 1;
+        while valid_from > broken {
+            let (a, b) = (path[valid_from - 1], path[valid_from]);
+            if map.is_obstacle(a) || !Self::is_step(map, a, b) {
+                break;
+            }
+            valid_from -= 1;
+        }
+        // Rejoin at the last occurrence of a position so loops get cut out
+        let rejoin: HashMap<IVec2, usize> = path
+            .iter()
+            .enumerate()
+            .skip(valid_from)
+            .map(|(i, p)| (*p, i))
+            .collect();
+
+        let goal_fn = |p: IVec2| p != from && rejoin.contains_key(&p);
+        let detour = self.astar_fn(map, from, goal_fn, None)?.to_vec();
+        let i = rejoin[detour.last()?];
+        self.path.clear();
+        self.path.extend_from_slice(&path[..broken - 1]);
+        self.path.extend_from_slice(&detour);
+        self.path.extend_from_slice(&path[i + 1..]);
+        Some(self.path.as_slice())
+    }
+
+    /// Find a path to a goal that avoids the paths of other agents using
+    /// [Windowed Hierarchical Cooperative A*].
+    ///
+    /// The search is performed over positions in both space and time, where each
+    /// step along the path takes one timestep and an agent can choose to wait in
+    /// place. Waiting costs the same as `map.cost(p, p)`. Any position or move
+    /// that is reserved in `reservations` at the time it would be used is avoided.
+    ///
+    /// `start_time` is the timestep of the first point in the path. Reservations
+    /// are only considered for `window` timesteps after that. If the goal isn't
+    /// reached within the window then the returned path is a partial path that
+    /// ends at the most promising position at the end of the window, and should
+    /// be planned again once the agent gets there. This happens even if the goal
+    /// is unreachable, so check whether the last point is the goal.
+    ///
+    /// Returns a slice of points where each point is the agent's position at the
+    /// next timestep, or [None] if no path can be found. Once the path is
+    /// accepted it should be added to the reservation table via
+    /// [ReservationTable::reserve_path].
+    ///
+    /// [Windowed Hierarchical Cooperative A*]: https://www.davidsilver.uk/wp-content/uploads/2020/03/coop-path-AIIDE.pdf
+    pub fn astar_reserved(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: impl Into<IVec2>,
+        goal: impl Into<IVec2>,
+        start_time: u32,
+        window: u32,
+        reservations: &ReservationTable,
+    ) -> Option<&[IVec2]> {
+        self.clear();
+        let start = start.into();
+        let goal = goal.into();
+        let end_time = start_time.saturating_add(window);
+
+        let path = self.timed.astar(
+            (start, start_time),
+            |(p, t)| {
+                (p == goal && !reservations.is_reserved_after(p, t.saturating_add(1)))
+                    || t >= end_time
+            },
+            |(p, t)| {
+                std::iter::once((p, map.cost(p, p)))
+                    .chain(map.exits_with_cost(p))
+                    .filter(move |(next, _)| !reservations.is_move_blocked(p, *next, t))
+                    .map(move |(next, cost)| ((next, t + 1), cost))
+            },
+            |(p, _)| map.distance(goal, p),
+        )?;
+        self.path.extend(path.iter().map(|(p, _)| *p));
+        Some(self.path.as_slice())
+    }
+
+    /// Find a path to a goal using the [A*] algorithm, where the cost of each move
+    /// depends on the time it's made, as given by [PathMap::cost_at].
+    ///
+    /// Time starts at `start_time` and advances by the cost of each move, rounded
+    /// up to a whole timestep. Every move or wait takes at least one timestep,
+    /// even if it costs nothing. The path may wait in place to avoid moving
+    /// obstacles such as patrolling guards or timed doors. The search gives up
+    /// on paths that arrive later than `start_time + max_time`.
+    ///
+    /// Returns a slice of points representing the path, or [None] if no path
+    /// can be found. Waiting appears as the same point repeated, and the time
+    /// each point is reached can be retrieved via [Pathfinder::arrival_times].
+    ///
+    /// [A*]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#astar
+    pub fn astar_timed(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: impl Into<IVec2>,
+        goal: impl Into<IVec2>,
+        start_time: u32,
+        max_time: u32,
+    ) -> Option<&[IVec2]> {
+        self.clear();
+        let start = start.into();
+        let goal = goal.into();
+        let end_time = start_time.saturating_add(max_time);
+
+        let path = self.timed.astar(
+            (start, start_time),
+            |(p, _)| p == goal,
+            |(p, t)| {
+                std::iter::once(p)
+                    .chain(map.exits(p))
+                    .filter_map(move |next| {
+                        let cost = map.cost_at(p, next, t)?;
+                        let steps = (cost.to_f32().ceil() as u32).max(1);
+                        let arrival = t.checked_add(steps)?;
+                        (arrival <= end_time).then_some(((next, arrival), cost))
+                    })
+            },
+            |(p, _)| map.distance(goal, p),
+        )?;
+        self.path.extend(path.iter().map(|(p, _)| *p));
+        self.arrivals.extend(path.iter().map(|(_, t)| *t));
+        Some(self.path.as_slice())
+    }
+
+    /// Find a path to a goal using the [A*] algorithm, where changing direction
+    /// along the path has an additional cost.
+    ///
+    /// The search is performed over both position and the direction the path
+    /// arrived from. `turn_cost` is called with the incoming and outgoing direction
+    /// of each move and returns the extra cost of making that turn, which must not
+    /// be negative. [crate::pathmap::TurnPenalties] can be used for a simple
+    /// angle based cost. An optional starting `heading` can be provided, otherwise
+    /// the first move is free.
+    ///
+    /// Returns a slice of points representing the path, or [None] if no path
+    /// can be found.
+    ///
+    /// [A*]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#astar
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::{*, pathmap::TurnPenalties};
+    /// let mut pf = Pathfinder::new();
+    /// let map = PathMap2d::new([10, 10]);
+    /// let penalties = TurnPenalties {
+    ///     turn_45: 1,
+    ///     turn_90: 4,
+    ///     turn_135: 8,
+    ///     turn_180: 16,
+    /// };
+    /// let path = pf
+    ///     .astar_turning(&map, [0, 0], None, [5, 5], |a, b| penalties.cost(a, b))
+    ///     .unwrap();
+    /// assert_eq!(6, path.len());
+    /// ```
+    pub fn astar_turning(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: impl Into<IVec2>,
+        heading: Option<IVec2>,
+        goal: impl Into<IVec2>,
+        turn_cost: impl Fn(IVec2, IVec2) -> C,
+    ) -> Option<&[IVec2]> {
+        self.clear();
+        let start = start.into();
+        let goal = goal.into();
+        let heading = heading.map(|h| h.signum()).unwrap_or(IVec2::ZERO);
+
+        let path = self.headings.astar(
+            (start, heading),
+            |(p, _)| p == goal,
+            |(p, incoming)| {
+                let turn_cost = &turn_cost;
+                map.exits_with_cost(p).map(move |(next, mut cost)| {
+                    let outgoing = (next - p).signum();
+                    if incoming != IVec2::ZERO {
+                        cost = cost + turn_cost(incoming, outgoing);
+                    }
+                    ((next, outgoing), cost)
+                })
+            },
+            |(p, _)| map.distance(goal, p),
+        )?;
+        self.path.extend(path.iter().map(|(p, _)| *p));
+        Some(self.path.as_slice())
+    }
+
     /// Find a path to a goal using [Dijkstra's Algorithm]. Note that if
     /// the movement cost is uniform across your entire map then you are better
     /// off using [Pathfinder::bfs] instead as it will be faster and give
@@ -106,7 +594,7 @@ impl Pathfinder {
     /// [Dijkstra's Algorithm]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#dijkstra
     pub fn dijkstra(
         &mut self,
-        map: &impl PathMap,
+        map: &impl PathMap<Cost = C>,
         start: Option<impl Into<IVec2>>,
         goal: impl Into<IVec2>,
     ) {
@@ -116,15 +604,15 @@ impl Pathfinder {
         let goal = goal.into();
 
         let p = start.unwrap_or(goal);
-        self.frontier.push(p, 0);
-        self.costs.insert(p, 0);
+        self.frontier.push(p, C::ZERO);
+        self.costs.insert(p, C::ZERO);
 
         while let Some(curr) = self.frontier.pop() {
             if start.is_some() && curr == goal {
                 break;
             }
-            for next in map.exits(curr) {
-                let new_cost = self.costs[&curr] + map.cost(curr, next);
+            for (next, cost) in map.exits_with_cost(curr) {
+                let new_cost = self.costs[&curr] + cost;
 
                 let next_cost = self.costs.get(&next);
                 if next_cost.is_none() || new_cost < *next_cost.unwrap() {
@@ -142,10 +630,13 @@ impl Pathfinder {
     /// `goal`. Otherwise it will run until all possible nodes have been visited.
     /// Afterwards, a path can be retrieved via [Pathfinder::build_path].
     ///
+    /// Movement costs from the [PathMap] are ignored. The number of steps
+    /// taken to reach each visited node is stored in [Pathfinder::costs].
+    ///
     /// [Breadth First Search]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#breadth-first-search
     pub fn bfs(
         &mut self,
-        map: &impl PathMap,
+        map: &impl PathMap<Cost = C>,
         start: Option<impl Into<IVec2>>,
         goal: impl Into<IVec2>,
     ) {
@@ -155,15 +646,167 @@ impl Pathfinder {
         let goal = goal.into();
 
         let p = start.unwrap_or(goal);
-        self.frontier.push(p, 0);
+        self.queue.push_back(p);
+        self.costs.insert(p, C::ZERO);
 
-        while let Some(curr) = self.frontier.pop() {
+        while let Some(curr) = self.queue.pop_front() {
             if start.is_some() && curr == goal {
                 break;
             }
+            let steps = self.costs[&curr] + C::ONE;
+            for next in map.exits(curr) {
+                if let Entry::Vacant(e) = self.costs.entry(next) {
+                    e.insert(steps);
+                    self.queue.push_back(next);
+                    self.came_from.insert(next, curr);
+                }
+            }
+        }
+    }
+
+    /// Find every position that can be reached from `start` with a total
+    /// movement cost of at most `budget`.
+    ///
+    /// Returns an iterator of each reachable position along with the cost to
+    /// reach it, including `start` itself at a cost of 0. Only positions within
+    /// the budget are explored. Afterwards a path to any of the reachable positions
+    /// can be retrieved via [Pathfinder::build_path].
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let mut pf = Pathfinder::new();
+    /// let mut map = PathMap2d::new([10, 10]);
+    /// map.adjacency = pathmap::Adjacency::Cardinal;
+    /// let reachable: Vec<_> = pf.reachable_within(&map, [5, 5], 1).collect();
+    /// assert_eq!(5, reachable.len());
+    /// let path = pf.build_path([5, 5], [5, 6]).unwrap();
+    /// assert_eq!(2, path.len());
+    /// ```
+    pub fn reachable_within(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        start: impl Into<IVec2>,
+        budget: C,
+    ) -> impl Iterator<Item = (IVec2, C)> + '_ {
+        self.clear();
+
+        let start = start.into();
+        self.frontier.push(start, C::ZERO);
+        self.costs.insert(start, C::ZERO);
+
+        while let Some(curr) = self.frontier.pop() {
+            for (next, cost) in map.exits_with_cost(curr) {
+                let new_cost = self.costs[&curr] + cost;
+                if new_cost > budget {
+                    continue;
+                }
+
+                let next_cost = self.costs.get(&next);
+                if next_cost.is_none() || new_cost < *next_cost.unwrap() {
+                    self.costs.insert(next, new_cost);
+                    self.frontier.push(next, new_cost);
+                    self.came_from.insert(next, curr);
+                }
+            }
+        }
+
+        self.costs.iter().map(|(p, c)| (*p, *c))
+    }
+
+    /// Run [Dijkstra's Algorithm] outwards from multiple sources at once until
+    /// all possible nodes have been visited.
+    ///
+    /// Every visited node is assigned to the source that can reach it most
+    /// cheaply, which can be retrieved via [Pathfinder::source]. A path from
+    /// the nearest source to any node can be retrieved via [Pathfinder::build_path].
+    ///
+    /// [Dijkstra's Algorithm]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#dijkstra
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let mut pf = Pathfinder::new();
+    /// let map = PathMap2d::new([10, 10]);
+    /// pf.dijkstra_multi(&map, [[0, 0], [9, 9]]);
+    /// assert_eq!([0, 0], pf.source([2, 3]).unwrap().to_array());
+    /// assert_eq!([9, 9], pf.source([8, 6]).unwrap().to_array());
+    /// ```
+    pub fn dijkstra_multi(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        sources: impl IntoIterator<Item = impl Into<IVec2>>,
+    ) {
+        self.dijkstra_multi_with_costs(map, sources.into_iter().map(|s| (s, C::ZERO)));
+    }
+
+    /// Run [Dijkstra's Algorithm] outwards from multiple sources at once, where
+    /// each source has an initial cost.
+    ///
+    /// A source with a higher initial cost will claim fewer nodes than
+    /// a source with a lower initial cost. See [Pathfinder::dijkstra_multi].
+    ///
+    /// [Dijkstra's Algorithm]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#dijkstra
+    pub fn dijkstra_multi_with_costs(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        sources: impl IntoIterator<Item = (impl Into<IVec2>, C)>,
+    ) {
+        self.clear();
+
+        for (p, cost) in sources {
+            let p = p.into();
+            self.frontier.push(p, cost);
+            self.costs.insert(p, cost);
+            self.sources.insert(p, p);
+        }
+
+        while let Some(curr) = self.frontier.pop() {
+            let source = self.sources[&curr];
+            for (next, cost) in map.exits_with_cost(curr) {
+                let new_cost = self.costs[&curr] + cost;
+
+                let next_cost = self.costs.get(&next);
+                if next_cost.is_none() || new_cost < *next_cost.unwrap() {
+                    self.costs.insert(next, new_cost);
+                    self.frontier.push(next, new_cost);
+                    self.came_from.insert(next, curr);
+                    self.sources.insert(next, source);
+                }
+            }
+        }
+    }
+
+    /// Run a [Breadth First Search] outwards from multiple sources at once until
+    /// all possible nodes have been visited.
+    ///
+    /// Every visited node is assigned to the source that can reach it in the
+    /// fewest steps, which can be retrieved via [Pathfinder::source]. A path from
+    /// the nearest source to any node can be retrieved via [Pathfinder::build_path].
+    ///
+    /// [Breadth First Search]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#breadth-first-search
+    pub fn bfs_multi(
+        &mut self,
+        map: &impl PathMap<Cost = C>,
+        sources: impl IntoIterator<Item = impl Into<IVec2>>,
+    ) {
+        self.clear();
+
+        for p in sources {
+            let p = p.into();
+            self.queue.push_back(p);
+            self.costs.insert(p, C::ZERO);
+            self.sources.insert(p, p);
+        }
+
+        while let Some(curr) = self.queue.pop_front() {
+            let source = self.sources[&curr];
+            let steps = self.costs[&curr] + C::ONE;
             for next in map.exits(curr) {
-                if let Entry::Vacant(_) = self.came_from.entry(next) {
-                    self.frontier.push(next, self.frontier.len() as i32);
+                if let Entry::Vacant(e) = self.sources.entry(next) {
+                    e.insert(source);
+                    self.queue.push_back(next);
+                    self.costs.insert(next, steps);
                     self.came_from.insert(next, curr);
                 }
             }
@@ -205,9 +848,14 @@ impl Pathfinder {
     /// Clear all internal data.
     pub fn clear(&mut self) {
         self.frontier.clear();
+        self.queue.clear();
         self.came_from.clear();
         self.costs.clear();
+        self.sources.clear();
+        self.timed.clear();
+        self.headings.clear();
         self.path.clear();
+        self.arrivals.clear();
     }
 
     /// An iterator over all nodes visited during pathfinding.
@@ -223,20 +871,41 @@ impl Pathfinder {
 
     /// Retrieve a reference to the `costs` map which is populated during
     /// pathfinding operations.
-    pub fn costs(&self) -> &HashMap<IVec2, i32> {
+    pub fn costs(&self) -> &HashMap<IVec2, C> {
         &self.costs
     }
 
+    /// The source a position was reached from during the most recent multi-source
+    /// search, [Pathfinder::dijkstra_multi] or [Pathfinder::bfs_multi].
+    pub fn source(&self, p: impl Into<IVec2>) -> Option<IVec2> {
+        self.sources.get(&p.into()).copied()
+    }
+
+    /// Retrieve a reference to the `sources` map which is populated during
+    /// multi-source pathfinding operations.
+    pub fn sources(&self) -> &HashMap<IVec2, IVec2> {
+        &self.sources
+    }
+
     /// Retrieve a slice of the most recently built path data. If no path
     /// has been built, the slice will be empty.
     pub fn path(&self) -> &[IVec2] {
         &self.path
     }
+
+    /// The arrival time at each point of the most recent path found by
+    /// [Pathfinder::astar_timed]. Empty for any other search.
+    pub fn arrival_times(&self) -> &[u32] {
+        &self.arrivals
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::PathMap2d;
+    use crate::{
+        pathmap::{Adjacency, TurnPenalties},
+        PathMap2d,
+    };
 
     use super::*;
 
@@ -287,4 +956,479 @@ mod test {
         assert_eq!([9, 5], path[0].to_array());
         assert_eq!([4, 5], path[5].to_array());
     }
+
+    #[test]
+    fn reachable_within() {
+        let mut map = PathMap2d::new([10, 10]);
+        map.adjacency = Adjacency::Cardinal;
+        map.add_obstacle([4, 5]);
+
+        let mut pf = Pathfinder::new();
+        let reachable: HashMap<_, _> = pf.reachable_within(&map, [5, 5], 3).collect();
+
+        assert_eq!(Some(&0), reachable.get(&IVec2::new(5, 5)));
+        assert_eq!(Some(&3), reachable.get(&IVec2::new(8, 5)));
+        assert_eq!(Some(&3), reachable.get(&IVec2::new(3, 6)));
+        assert!(!reachable.contains_key(&IVec2::new(4, 5)));
+        assert!(!reachable.contains_key(&IVec2::new(9, 5)));
+        assert!(!reachable.contains_key(&IVec2::new(2, 5)));
+        assert!(reachable.values().all(|c| *c <= 3));
+
+        let path = pf.build_path([5, 5], [3, 6]).unwrap();
+        assert_eq!(4, path.len());
+    }
+
+    #[test]
+    fn astar_multi() {
+        let mut map = PathMap2d::new([10, 10]);
+        for y in 0..9 {
+            map.add_obstacle([2, y]);
+        }
+
+        let mut pf = Pathfinder::new();
+        // [3,0] is closer as the crow flies, but the wall makes [0,5] cheaper
+        let goals = [IVec2::new(3, 0), IVec2::new(0, 5)];
+        let path = pf.astar_multi(&map, [0, 0], &goals).unwrap();
+        assert_eq!([0, 5], path.last().unwrap().to_array());
+        assert_eq!(6, path.len());
+
+        let path = pf
+            .astar_fn(&map, [0, 0], |p| p.x > 2, None)
+            .unwrap()
+            .to_vec();
+        assert_eq!(3, path.last().unwrap().x);
+
+        assert!(pf.astar_multi(&map, [0, 0], &[]).is_none());
+    }
+
+    /// A corridor with a door at (5, 0) that's only open from time 20.
+    struct TimedDoor(PathMap2d);
+
+    impl PathMap for TimedDoor {
+        type ExitIterator = <PathMap2d as PathMap>::ExitIterator;
+        type Cost = i32;
+
+        fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+            self.0.exits(p)
+        }
+
+        fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
+            self.0.cost(a, b)
+        }
+
+        fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
+            self.0.distance(a, b)
+        }
+
+        fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+            self.0.is_obstacle(p)
+        }
+
+        fn cost_at(&self, a: impl Into<IVec2>, b: impl Into<IVec2>, t: u32) -> Option<i32> {
+            let (a, b) = (a.into(), b.into());
+            let cost = self.cost(a, b);
+            (b != IVec2::new(5, 0) || t + cost as u32 >= 20).then_some(cost)
+        }
+    }
+
+    #[test]
+    fn timed() {
+        let mut map = PathMap2d::new([10, 1]);
+        map.adjacency = Adjacency::Cardinal;
+        let mut pf = Pathfinder::new();
+
+        // Uses the regular costs by default
+        let path = pf.astar_timed(&map, [0, 0], [9, 0], 0, 100).unwrap();
+        assert_eq!(10, path.len());
+        assert_eq!(9, *pf.arrival_times().last().unwrap());
+
+        let map = TimedDoor(map);
+        let path = pf.astar_timed(&map, [0, 0], [9, 0], 0, 100).unwrap();
+        assert!(path.len() > 10);
+        let times = pf.arrival_times();
+        let door = pf
+            .path()
+            .iter()
+            .position(|p| *p == IVec2::new(5, 0))
+            .unwrap();
+        assert!(times[door] >= 20);
+        assert_eq!(24, *times.last().unwrap());
+
+        // Starting later means no need to wait
+        let path = pf.astar_timed(&map, [0, 0], [9, 0], 20, 100).unwrap();
+        assert_eq!(10, path.len());
+
+        // The door doesn't open in time
+        assert!(pf.astar_timed(&map, [0, 0], [9, 0], 0, 15).is_none());
+    }
+
+    #[test]
+    fn repair() {
+        let mut map = PathMap2d::new([20, 10]);
+        let mut pf = Pathfinder::new();
+        let path = pf.astar(&map, [0, 5], [19, 5]).unwrap().to_vec();
+        assert_eq!(None, pf.validate(&map, &path));
+
+        // A short wall across the path
+        for y in 3..8 {
+            map.add_obstacle([10, y]);
+        }
+        let broken = pf.validate(&map, &path).unwrap();
+        assert_eq!(IVec2::new(10, 5), path[broken]);
+
+        let repaired = pf.repair(&map, &path).unwrap().to_vec();
+        assert_eq!(None, pf.validate(&map, &repaired));
+        assert_eq!(path[..broken - 1], repaired[..broken - 1]);
+        assert_eq!(path.last(), repaired.last());
+        // The repaired path rejoins the original rather than replanning to the end
+        let rejoined = repaired.iter().position(|p| p.x > 10 && p.y == 5).unwrap();
+        assert_eq!(
+            path[path.len() - (repaired.len() - rejoined)..],
+            repaired[rejoined..]
+        );
+
+        // Blocking the end of the path
+        map.add_obstacle([19, 5]);
+        assert!(pf.repair(&map, &path).is_none());
+        map.remove_obstacle([19, 5]);
+
+        // The start is blocked
+        map.add_obstacle([0, 5]);
+        assert_eq!(Some(0), pf.validate(&map, &path));
+        assert!(pf.repair(&map, &path).is_none());
+    }
+
+    #[test]
+    fn reserved() {
+        let mut map = PathMap2d::from_string(
+            "
+#####.#
+.......
+",
+            '#',
+        )
+        .unwrap();
+        map.adjacency = Adjacency::Cardinal;
+
+        let mut pf = Pathfinder::new();
+        let mut reservations = ReservationTable::new();
+        let a = pf
+            .astar_reserved(&map, [0, 0], [6, 0], 0, 20, &reservations)
+            .unwrap()
+            .to_vec();
+        assert_eq!(7, a.len());
+        reservations.reserve_path(&a, 0);
+
+        let b = pf
+            .astar_reserved(&map, [6, 0], [0, 0], 0, 20, &reservations)
+            .unwrap()
+            .to_vec();
+        assert_eq!([0, 0], b.last().unwrap().to_array());
+        assert!(b.contains(&IVec2::new(5, 1)));
+
+        for t in 0..a.len().max(b.len()) {
+            let pa = a[t.min(a.len() - 1)];
+            let pb = b[t.min(b.len() - 1)];
+            assert_ne!(pa, pb, "Collision at {}", t);
+            if t > 0 {
+                let swapped = a.get(t - 1) == Some(&pb) && b.get(t - 1) == Some(&pa);
+                assert!(!swapped, "Swap at {}", t);
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_window() {
+        let map = PathMap2d::new([20, 20]);
+        let mut pf = Pathfinder::new();
+        let reservations = ReservationTable::new();
+        let path = pf
+            .astar_reserved(&map, [0, 0], [15, 0], 0, 5, &reservations)
+            .unwrap();
+        assert_eq!(6, path.len());
+        assert_eq!([5, 0], path.last().unwrap().to_array());
+    }
+
+    #[test]
+    fn turning() {
+        let map = PathMap2d::new([10, 10]);
+        let mut pf = Pathfinder::new();
+        let penalties = TurnPenalties {
+            turn_45: 10,
+            turn_90: 20,
+            turn_135: 30,
+            turn_180: 40,
+        };
+        let turns = |path: &[IVec2]| {
+            path.windows(3)
+                .filter(|w| (w[1] - w[0]) != (w[2] - w[1]))
+                .count()
+        };
+
+        // Without penalties the path is free to zig-zag, with them it should
+        // only turn once
+        let path = pf
+            .astar_turning(&map, [0, 0], None, [6, 2], |_, _| 0)
+            .unwrap()
+            .to_vec();
+        assert_eq!(7, path.len());
+        let path = pf
+            .astar_turning(&map, [0, 0], None, [6, 2], |a, b| penalties.cost(a, b))
+            .unwrap();
+        assert_eq!(7, path.len());
+        assert_eq!(1, turns(path));
+
+        // Facing away from the goal, turning around on the spot costs more than
+        // a wider arc
+        let penalties = TurnPenalties {
+            turn_180: 100,
+            ..penalties
+        };
+        let path = pf
+            .astar_turning(&map, [5, 5], Some(IVec2::X), [4, 5], |a, b| {
+                penalties.cost(a, b)
+            })
+            .unwrap();
+        assert!(path.len() > 2);
+    }
+
+    #[test]
+    fn multi_source() {
+        let mut map = PathMap2d::new([10, 3]);
+        map.adjacency = Adjacency::Cardinal;
+
+        let mut pf = Pathfinder::new();
+        pf.dijkstra_multi(&map, [[0, 1], [9, 1]]);
+        assert_eq!(Some(IVec2::new(0, 1)), pf.source([4, 0]));
+        assert_eq!(Some(IVec2::new(9, 1)), pf.source([5, 2]));
+        assert_eq!(4, pf.costs()[&IVec2::new(4, 1)]);
+        let path = pf.build_path([9, 1], [6, 1]).unwrap();
+        assert_eq!(4, path.len());
+
+        pf.dijkstra_multi_with_costs(&map, [([0, 1], 0), ([9, 1], 4)]);
+        assert_eq!(Some(IVec2::new(0, 1)), pf.source([6, 1]));
+        assert_eq!(Some(IVec2::new(9, 1)), pf.source([8, 1]));
+
+        pf.bfs_multi(&map, [[0, 1], [9, 1]]);
+        assert_eq!(Some(IVec2::new(0, 1)), pf.source([3, 2]));
+        assert_eq!(Some(IVec2::new(9, 1)), pf.source([6, 0]));
+        assert_eq!(30, pf.sources().len());
+    }
+
+    fn uniform_map() -> PathMap2d {
+        let mut map = PathMap2d::from_string(
+            "
+..........
+.####.###.
+....#...#.
+.##.###.#.
+..#.....#.
+#.#.###...
+..#...#.#.
+.####.#.#.
+......#...
+",
+            '#',
+        )
+        .unwrap();
+        map.adjacency = Adjacency::Cardinal;
+        map
+    }
+
+    #[test]
+    fn bfs_matches_dijkstra() {
+        let map = uniform_map();
+        let start = IVec2::new(0, 0);
+        let mut bfs = Pathfinder::new();
+        let mut dijkstra = Pathfinder::new();
+
+        bfs.bfs(&map, None::<IVec2>, start);
+        dijkstra.dijkstra(&map, None::<IVec2>, start);
+
+        let costs = dijkstra.costs().clone();
+        assert_eq!(costs.len(), bfs.costs().len());
+        for (p, cost) in costs.iter() {
+            assert_eq!(*cost, bfs.costs()[p], "{}", p);
+            let a = dijkstra.build_path(start, *p).map(|p| p.len());
+            let b = bfs.build_path(start, *p).map(|p| p.len());
+            assert_eq!(a, b, "{}", p);
+        }
+    }
+
+    #[test]
+    fn bfs_start_goal() {
+        let map = uniform_map();
+        let mut bfs = Pathfinder::new();
+        let mut dijkstra = Pathfinder::new();
+
+        for goal in [[9, 0], [5, 6], [3, 3], [9, 8]] {
+            bfs.bfs(&map, Some([0, 8]), goal);
+            dijkstra.dijkstra(&map, Some([0, 8]), goal);
+            let a = bfs.build_path([0, 8], goal).unwrap().len();
+            let b = dijkstra.build_path([0, 8], goal).unwrap().len();
+            assert_eq!(a, b);
+            assert_eq!(a as i32 - 1, bfs.costs()[&IVec2::from(goal)]);
+        }
+    }
+
+    #[test]
+    fn start_is_goal() {
+        let map = PathMap2d::new([10, 10]);
+        let mut pf = Pathfinder::new();
+        let start = IVec2::new(3, 3);
+
+        assert_eq!([start], pf.astar(&map, start, start).unwrap());
+        assert_eq!(
+            [start],
+            pf.astar_fn(&map, start, |p| p.x == 3, None).unwrap()
+        );
+        assert_eq!([start], pf.astar_multi(&map, start, &[start]).unwrap());
+    }
+
+    #[test]
+    fn reserved_unreachable() {
+        let mut map = PathMap2d::new([10, 10]);
+        map.adjacency = Adjacency::Cardinal;
+        for y in 0..10 {
+            map.add_obstacle([5, y]);
+        }
+        let reservations = ReservationTable::new();
+        let mut pf = Pathfinder::new();
+
+        // The goal is unreachable, but the window expires first so a partial
+        // path is returned
+        let path = pf
+            .astar_reserved(&map, [0, 0], [9, 0], 0, 6, &reservations)
+            .unwrap();
+        assert_eq!(7, path.len());
+        assert_eq!([4, 0], path.last().unwrap().to_array());
+
+        // The window is cut short at the last timestep rather than overflowing
+        let path = pf
+            .astar_reserved(&map, [0, 0], [3, 0], u32::MAX - 1, 10, &reservations)
+            .unwrap();
+        assert_eq!(2, path.len());
+    }
+
+    #[test]
+    fn timed_zero_cost() {
+        let map = crate::FnPathMap::new(|p: IVec2| {
+            [IVec2::X, IVec2::NEG_X]
+                .map(|d| p + d)
+                .into_iter()
+                .filter(|n| (0..10).contains(&n.x))
+        })
+        .with_cost(|_, _| 0);
+        let mut pf = Pathfinder::new();
+        let path = pf.astar_timed(&map, [0, 0], [5, 0], 0, 20).unwrap();
+        assert_eq!(6, path.len());
+        assert_eq!([0, 1, 2, 3, 4, 5], pf.arrival_times());
+        assert!(pf.astar_timed(&map, [0, 0], [5, 0], 0, 4).is_none());
+    }
+
+    #[test]
+    fn turning_float() {
+        let map = PathMap2d::with_adjacency(
+            [10, 10],
+            Adjacency::Octile {
+                cardinal_cost: 1.0,
+                diagonal_cost: std::f32::consts::SQRT_2,
+            },
+        );
+        let penalties = TurnPenalties {
+            turn_45: 0.5,
+            turn_90: 1.5,
+            turn_135: 2.5,
+            turn_180: 3.5,
+        };
+        let mut pf = Pathfinder::new();
+        let path = pf
+            .astar_turning(&map, [0, 0], Some(IVec2::X), [5, 0], |a, b| {
+                penalties.cost(a, b)
+            })
+            .unwrap();
+        assert!(path.iter().all(|p| p.y == 0));
+        assert_eq!(0.0, penalties.cost(IVec2::X, IVec2::X));
+    }
+
+    #[test]
+    fn float_costs() {
+        use std::f32::consts::SQRT_2;
+        let mut map = PathMap2d::with_adjacency(
+            [10, 10],
+            Adjacency::Octile {
+                cardinal_cost: 1.0,
+                diagonal_cost: SQRT_2,
+            },
+        );
+        map.add_obstacle([2, 2]);
+
+        let mut pf = Pathfinder::new();
+        let path = pf.astar(&map, [0, 0], [5, 3]).unwrap().to_vec();
+
+        let cost: f32 = path.windows(2).map(|w| map.cost(w[0], w[1])).sum();
+        assert!((cost - (3.0 * SQRT_2 + 2.0)).abs() < 0.001);
+        assert!((pf.costs()[&IVec2::new(5, 3)] - cost).abs() < 0.001);
+    }
+
+    #[test]
+    fn tie_breaks() {
+        let mut map = PathMap2d::new([5, 5]);
+        map.adjacency = Adjacency::Cardinal;
+        let path = |tie_break| {
+            let mut pf = Pathfinder::new().with_tie_break(tie_break);
+            let path: Vec<_> = pf
+                .astar(&map, [4, 2], [0, 0])
+                .unwrap()
+                .iter()
+                .map(|p| p.to_array())
+                .collect();
+            (path, pf.costs().len())
+        };
+
+        // Prefers higher y, so moves along the top first
+        let (position, position_visited) = path(TieBreak::Position);
+        assert_eq!(
+            vec![[4, 2], [3, 2], [2, 2], [1, 2], [0, 2], [0, 1], [0, 0]],
+            position
+        );
+        // The same path, found with fewer positions visited
+        let (lower_h, lower_h_visited) = path(TieBreak::LowerH);
+        assert_eq!(position, lower_h);
+        assert_eq!((20, 16), (position_visited, lower_h_visited));
+        // Prefers whichever position was found first
+        assert_eq!(
+            vec![[4, 2], [4, 1], [4, 0], [3, 0], [2, 0], [1, 0], [0, 0]],
+            path(TieBreak::Fifo).0
+        );
+        // Stays close to the straight line from start to goal
+        assert_eq!(
+            vec![[4, 2], [3, 2], [3, 1], [2, 1], [1, 1], [1, 0], [0, 0]],
+            path(TieBreak::CrossProduct).0
+        );
+    }
+
+    #[test]
+    fn validate_waits() {
+        let mut map = PathMap2d::new([10, 1]);
+        map.adjacency = Adjacency::Cardinal;
+        let path: Vec<_> = [[0, 0], [1, 0], [1, 0], [1, 0], [2, 0], [3, 0]]
+            .map(IVec2::from)
+            .into();
+        let mut pf = Pathfinder::new();
+        assert_eq!(None, pf.validate(&map, &path));
+        assert_eq!(path, pf.repair(&map, &path).unwrap());
+
+        let door = TimedDoor(map);
+        let timed = pf
+            .astar_timed(&door, [0, 0], [9, 0], 0, 100)
+            .unwrap()
+            .to_vec();
+        assert!(timed.windows(2).any(|w| w[0] == w[1]));
+        assert_eq!(None, pf.validate(&door, &timed));
+
+        let mut map = door.0;
+        map.add_obstacle([1, 0]);
+        assert_eq!(Some(1), pf.validate(&map, &path));
+    }
 }
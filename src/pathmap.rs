@@ -1,7 +1,7 @@
-use arrayvec::{ArrayVec, IntoIter};
+use arrayvec::ArrayVec;
 use glam::{IVec2, UVec2};
 
-use crate::{bit_grid::BitGrid, grid::SizedGrid};
+use crate::{bit_grid::BitGrid, cost::Cost, grid::SizedGrid, links::Links};
 
 pub const DEFAULT_MAX_EXITS: usize = 8;
 pub const DEFAULT_CARDINAL_COST: i32 = 2;
@@ -9,14 +9,34 @@ pub const DEFAULT_DIAGONAL_COST: i32 = 3;
 
 /// A trait for a map that defines pathing information across a 2d grid.
 pub trait PathMap {
+    /// The type used for movement costs and distances, such as `i32` or `f32`.
+    type Cost: Cost;
     type ExitIterator: Iterator<Item = IVec2>;
     /// Returns an iterator of the valid exits from the given grid point.
     fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator;
     /// The cost of moving between two grid points.
-    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32;
+    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> Self::Cost;
     /// The distance between two grid points.
-    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32;
+    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> Self::Cost;
     fn is_obstacle(&self, p: impl Into<IVec2>) -> bool;
+    /// The cost of moving between two grid points when leaving `a` at time `t`,
+    /// or [None] if the move isn't possible at that time. Moving from a point
+    /// to itself is waiting in place.
+    ///
+    /// Used by [crate::Pathfinder::astar_timed]. Defaults to [PathMap::cost].
+    fn cost_at(&self, a: impl Into<IVec2>, b: impl Into<IVec2>, t: u32) -> Option<Self::Cost> {
+        let _ = t;
+        Some(self.cost(a, b))
+    }
+    /// Returns an iterator of the valid exits from the given grid point along
+    /// with the cost of moving to each one. This is what the searches use, so
+    /// maps that can find both at once should override it.
+    ///
+    /// Defaults to calling [PathMap::cost] for each of [PathMap::exits].
+    fn exits_with_cost(&self, p: impl Into<IVec2>) -> impl Iterator<Item = (IVec2, Self::Cost)> {
+        let p = p.into();
+        self.exits(p).map(move |next| (next, self.cost(p, next)))
+    }
 }
 
 /// A basic pathmap that tracks obstacles. When building the map you can specify
@@ -33,37 +53,157 @@ pub trait PathMap {
 ///
 /// let path = pf.astar(&map, [4,4], [10,10]).unwrap();
 /// ```
-pub struct PathMap2d {
-    pub adjacency: Adjacency,
+///
+/// Positions that aren't adjacent can be connected with directed links, such
+/// as teleporters or stairs, via [PathMap2d::add_link]. Movement between adjacent
+/// positions can be blocked in one or both directions, for thin walls or one way
+/// ledges, via [PathMap2d::block_edge] and [PathMap2d::add_wall].
+///
+/// Movement costs are `i32` by default. Other [Cost] types such as `f32` can be
+/// used by creating the map with [PathMap2d::with_adjacency].
+pub struct PathMap2d<C = i32> {
+    pub adjacency: Adjacency<C>,
     obstacles: BitGrid,
+    links: Links<C>,
+    /// For each position, a bit for each direction in [DIR_8] which is set if
+    /// movement in that direction is blocked. Empty if no edges are blocked.
+    blocked_edges: Vec<u8>,
+    /// Positions changed since `changes_start`, see [PathMap2d::changes_since].
+    changes: Vec<IVec2>,
+    changes_start: u64,
 }
 
 /// Defines how the grid handles movement between adjacent tiles.
-pub enum Adjacency {
+pub enum Adjacency<C = i32> {
     /// Grid allows for 4-way movement.
     Cardinal,
     /// Grid allows for 8-way movement.
-    Octile {
-        cardinal_cost: i32,
-        diagonal_cost: i32,
-    },
+    Octile { cardinal_cost: C, diagonal_cost: C },
+    /// Grid allows for 8-way movement where diagonal moves cost the same as
+    /// cardinal moves.
+    Chebyshev { cost: C },
+    /// Grid allows for 8-way movement where diagonal moves cost `cost * sqrt(2)`.
+    ///
+    /// For integer costs the diagonal cost is rounded down, so a larger `cost`
+    /// such as 10 or 100 gives a closer approximation.
+    Euclidean { cost: C },
+    /// Grid allows movement by each of the given offsets with the given cost,
+    /// such as knight moves or a 16 way neighbourhood.
+    ///
+    /// Offsets don't need to be adjacent. Only the destination of a move is
+    /// checked for obstacles.
+    Custom(Vec<(IVec2, C)>),
 }
 
-impl Default for Adjacency {
+impl<C: Cost> Default for Adjacency<C> {
     fn default() -> Self {
         Self::Octile {
-            cardinal_cost: DEFAULT_CARDINAL_COST,
-            diagonal_cost: DEFAULT_DIAGONAL_COST,
+            cardinal_cost: C::from_i32(DEFAULT_CARDINAL_COST),
+            diagonal_cost: C::from_i32(DEFAULT_DIAGONAL_COST),
+        }
+    }
+}
+
+impl<C: Cost> Adjacency<C> {
+    /// A [Adjacency::Custom] neighbourhood of knight moves from chess, each
+    /// with the given cost.
+    pub fn knight(cost: C) -> Self {
+        Self::Custom(
+            [
+                [1, 2],
+                [2, 1],
+                [2, -1],
+                [1, -2],
+                [-1, -2],
+                [-2, -1],
+                [-2, 1],
+                [-1, 2],
+            ]
+            .into_iter()
+            .map(|d| (IVec2::from(d), cost))
+            .collect(),
+        )
+    }
+
+    /// The cost of a single move by the given offset.
+    ///
+    /// Offsets that aren't part of a [Adjacency::Custom] neighbourhood cost
+    /// [Cost::ONE].
+    pub fn move_cost(&self, offset: impl Into<IVec2>) -> C {
+        let d = offset.into();
+        let cardinal = d.x == 0 || d.y == 0;
+        match self {
+            Adjacency::Cardinal => C::ONE,
+            Adjacency::Octile {
+                cardinal_cost,
+                diagonal_cost,
+            } => {
+                if cardinal {
+                    *cardinal_cost
+                } else {
+                    *diagonal_cost
+                }
+            }
+            Adjacency::Chebyshev { cost } => *cost,
+            Adjacency::Euclidean { cost } => {
+                if cardinal {
+                    *cost
+                } else {
+                    euclidean_diagonal(*cost)
+                }
+            }
+            Adjacency::Custom(moves) => moves
+                .iter()
+                .find(|(m, _)| *m == d)
+                .map_or(C::ONE, |(_, c)| *c),
+        }
+    }
+
+    /// An estimate of the cost of moving between two points on an open grid
+    /// with this adjacency. Never overestimates.
+    pub fn heuristic(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> C {
+        let (a, b) = (a.into(), b.into());
+        match self {
+            Adjacency::Cardinal => C::from_i32(cardinal_heuristic(a, b)),
+            Adjacency::Octile {
+                cardinal_cost,
+                diagonal_cost,
+            } => octile_distance(a, b, *cardinal_cost, *diagonal_cost),
+            Adjacency::Chebyshev { cost } => *cost * C::from_i32((a - b).abs().max_element()),
+            Adjacency::Euclidean { cost } => {
+                octile_distance(a, b, *cost, euclidean_diagonal(*cost))
+            }
+            Adjacency::Custom(moves) => {
+                // The cheapest cost per unit of distance of any move, applied
+                // to the straight line distance.
+                let rate = moves
+                    .iter()
+                    .filter(|(m, _)| *m != IVec2::ZERO)
+                    .map(|(m, c)| c.to_f32() / m.as_vec2().length())
+                    .reduce(f32::min)
+                    .unwrap_or(0.0)
+                    .max(0.0);
+                C::from_f32(rate * (a - b).as_vec2().length())
+            }
         }
     }
 }
 
+/// The cost of a diagonal move for [Adjacency::Euclidean].
+fn euclidean_diagonal<C: Cost>(cost: C) -> C {
+    C::from_f32(cost.to_f32() * std::f32::consts::SQRT_2)
+}
+
 impl PathMap2d {
     /// Create a new PathMap with all values set to false (no obstacles).
     pub fn new(size: impl Into<UVec2>) -> Self {
         Self {
             obstacles: BitGrid::new(size),
             adjacency: Adjacency::default(),
+            links: Links::default(),
+            blocked_edges: Vec::new(),
+            changes: Vec::new(),
+            changes_start: 0,
         }
     }
 
@@ -89,8 +229,42 @@ impl PathMap2d {
         Some(Self {
             adjacency: Adjacency::default(),
             obstacles,
+            links: Links::default(),
+            blocked_edges: Vec::new(),
+            changes: Vec::new(),
+            changes_start: 0,
         })
     }
+}
+
+impl<C: Cost> PathMap2d<C> {
+    /// Create a new PathMap with no obstacles and the given [Adjacency], which
+    /// determines the [Cost] type of the map.
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::{pathmap::Adjacency, *};
+    /// let map = PathMap2d::with_adjacency(
+    ///     [10, 10],
+    ///     Adjacency::Octile {
+    ///         cardinal_cost: 1.0,
+    ///         diagonal_cost: std::f32::consts::SQRT_2,
+    ///     },
+    /// );
+    /// let mut pf = Pathfinder::new();
+    /// pf.astar(&map, [0, 0], [3, 3]).unwrap();
+    /// assert!((pf.costs()[&[3, 3].into()] - 3.0 * std::f32::consts::SQRT_2).abs() < 0.001);
+    /// ```
+    pub fn with_adjacency(size: impl Into<UVec2>, adjacency: Adjacency<C>) -> Self {
+        Self {
+            obstacles: BitGrid::new(size),
+            adjacency,
+            links: Links::default(),
+            blocked_edges: Vec::new(),
+            changes: Vec::new(),
+            changes_start: 0,
+        }
+    }
 
     pub fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
         self.obstacles.get(p)
@@ -105,18 +279,172 @@ impl PathMap2d {
     }
 
     pub fn set_obstacle(&mut self, p: impl Into<IVec2>, v: bool) {
+        let p = p.into();
         self.obstacles.set(p, v);
+        self.changed(p);
     }
 
     pub fn toggle_obstacle(&mut self, p: impl Into<IVec2>) {
+        let p = p.into();
         self.obstacles.toggle(p);
+        self.changed(p);
     }
 
     /// Remove an obstacle from one position and add an obstacle to another.
     /// Note this will ignore the current state of either position.
     pub fn move_obstacle(&mut self, old_pos: impl Into<IVec2>, new_pos: impl Into<IVec2>) {
-        self.obstacles.set(old_pos, false);
-        self.obstacles.set(new_pos, true);
+        self.set_obstacle(old_pos, false);
+        self.set_obstacle(new_pos, true);
+    }
+
+    /// Add a directed link from one position to another with the given movement
+    /// cost. The link will be included in the exits of `from` as long as `to` is
+    /// not an obstacle. If the positions are adjacent the link cost replaces the
+    /// normal movement cost.
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let mut map = PathMap2d::new([20, 20]);
+    /// map.add_link([0, 0], [19, 19], 1);
+    /// let mut pf = Pathfinder::new();
+    /// let path = pf.astar(&map, [1, 0], [18, 19]).unwrap();
+    /// assert_eq!(4, path.len());
+    /// ```
+    pub fn add_link(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>, cost: C) {
+        let (from, to) = (from.into(), to.into());
+        self.links.add(from, to, cost);
+        self.changed(from);
+        self.changed(to);
+    }
+
+    /// Remove a directed link from one position to another. Returns false if the
+    /// link didn't exist.
+    pub fn remove_link(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>) -> bool {
+        let (from, to) = (from.into(), to.into());
+        self.changed(from);
+        self.changed(to);
+        self.links.remove(from, to)
+    }
+
+    /// A reference to the [PathMap2d]'s links.
+    pub fn links(&self) -> &Links<C> {
+        &self.links
+    }
+
+    /// A mutable reference to the [PathMap2d]'s links. Since individual
+    /// changes can't be tracked this marks the whole map as changed.
+    pub fn links_mut(&mut self) -> &mut Links<C> {
+        self.changed_all();
+        &mut self.links
+    }
+
+    /// Block movement from one position to an adjacent position. Movement in
+    /// the opposite direction is unaffected.
+    ///
+    /// A diagonal move is also blocked if both of the routes around its corners
+    /// are blocked, so a line of walls can't be slipped through diagonally.
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let mut map = PathMap2d::new([10, 10]);
+    /// // A ledge that can be dropped down from but not climbed
+    /// map.block_edge([5, 4], [5, 5]);
+    /// let mut pf = Pathfinder::new();
+    /// assert_eq!(2, pf.astar(&map, [5, 5], [5, 4]).unwrap().len());
+    /// assert_eq!(3, pf.astar(&map, [5, 4], [5, 5]).unwrap().len());
+    /// ```
+    pub fn block_edge(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>) {
+        self.set_edge_blocked(from, to, true);
+    }
+
+    /// Unblock movement from one position to an adjacent position.
+    pub fn unblock_edge(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>) {
+        self.set_edge_blocked(from, to, false);
+    }
+
+    /// Set whether movement from one position to an adjacent position is blocked.
+    /// Positions that aren't adjacent or are out of bounds are ignored.
+    pub fn set_edge_blocked(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>, v: bool) {
+        let (from, to) = (from.into(), to.into());
+        let (Some(i), Some(dir)) = (self.try_xy_to_index(from), dir_index(to - from)) else {
+            return;
+        };
+        self.changed(from);
+        self.changed(to);
+        if self.blocked_edges.is_empty() {
+            if !v {
+                return;
+            }
+            self.blocked_edges = vec![0; self.area()];
+        }
+        if v {
+            self.blocked_edges[i] |= 1 << dir;
+        } else {
+            self.blocked_edges[i] &= !(1 << dir);
+        }
+    }
+
+    /// Add a thin wall between two adjacent positions, blocking movement in both
+    /// directions.
+    pub fn add_wall(&mut self, a: impl Into<IVec2>, b: impl Into<IVec2>) {
+        let (a, b) = (a.into(), b.into());
+        self.block_edge(a, b);
+        self.block_edge(b, a);
+    }
+
+    /// Remove a thin wall between two adjacent positions.
+    pub fn remove_wall(&mut self, a: impl Into<IVec2>, b: impl Into<IVec2>) {
+        let (a, b) = (a.into(), b.into());
+        self.unblock_edge(a, b);
+        self.unblock_edge(b, a);
+    }
+
+    /// Block every exit from a position except the one in the given direction,
+    /// such as for a conveyor belt.
+    pub fn set_one_way_tile(&mut self, p: impl Into<IVec2>, dir: impl Into<IVec2>) {
+        let (p, dir) = (p.into(), dir.into().signum());
+        for adj in DIR_8 {
+            self.set_edge_blocked(p, p + *adj, *adj != dir);
+        }
+    }
+
+    /// Whether movement directly from one position to an adjacent position has
+    /// been blocked.
+    pub fn is_edge_blocked(&self, from: impl Into<IVec2>, to: impl Into<IVec2>) -> bool {
+        if self.blocked_edges.is_empty() {
+            return false;
+        }
+        let (from, to) = (from.into(), to.into());
+        let (Some(i), Some(dir)) = (self.try_xy_to_index(from), dir_index(to - from)) else {
+            return false;
+        };
+        self.blocked_edges[i] & (1 << dir) != 0
+    }
+
+    /// Remove all blocked edges and thin walls.
+    pub fn clear_blocked_edges(&mut self) {
+        self.blocked_edges = Vec::new();
+        self.changed_all();
+    }
+
+    /// Whether the edges between two adjacent positions allow movement from
+    /// one to the other.
+    fn can_cross(&self, from: IVec2, to: IVec2) -> bool {
+        if self.blocked_edges.is_empty() {
+            return true;
+        }
+        if self.is_edge_blocked(from, to) {
+            return false;
+        }
+        let d = to - from;
+        if d.x == 0 || d.y == 0 || d.abs().max_element() > 1 {
+            return true;
+        }
+        let (c1, c2) = (from + IVec2::new(d.x, 0), from + IVec2::new(0, d.y));
+        let open = |c: IVec2| !self.is_edge_blocked(from, c) && !self.is_edge_blocked(c, to);
+        open(c1) || open(c2)
     }
 
     /// A reference to the underlying bit grid that stores the [PathMap2d]'s
@@ -126,11 +454,57 @@ impl PathMap2d {
     }
 
     /// A mutable reference to the underlying bit grid that stores the
-    /// [PathMap2d]'s obstacle data.
+    /// [PathMap2d]'s obstacle data. Since individual changes can't be tracked
+    /// this marks the whole map as changed.
     pub fn obstacle_grid_mut(&mut self) -> &mut BitGrid {
+        self.changed_all();
         &mut self.obstacles
     }
 
+    /// A counter which increases every time the map's obstacles, links or
+    /// blocked edges are changed.
+    pub fn generation(&self) -> u64 {
+        self.changes_start + self.changes.len() as u64
+    }
+
+    /// The positions changed since the map was at the given
+    /// [generation](PathMap2d::generation), which may contain duplicates.
+    ///
+    /// Returns `None` if the changes are no longer known, either because the
+    /// whole map was marked as changed or because so many changes were made
+    /// that older ones were discarded. In that case anything derived from the
+    /// map should be rebuilt.
+    ///
+    /// Changes to [PathMap2d::adjacency] are not tracked.
+    ///
+    /// # Example
+    /// ```
+    /// use sark_pathfinding::*;
+    /// let mut map = PathMap2d::new([10, 10]);
+    /// let gen = map.generation();
+    /// map.add_obstacle([1, 1]);
+    /// map.add_wall([3, 3], [3, 4]);
+    /// let changes = map.changes_since(gen).unwrap();
+    /// assert!(changes.contains(&[1, 1].into()));
+    /// assert!(changes.contains(&[3, 4].into()));
+    /// ```
+    pub fn changes_since(&self, generation: u64) -> Option<&[IVec2]> {
+        let i = generation.checked_sub(self.changes_start)?;
+        self.changes.get(i as usize..)
+    }
+
+    fn changed(&mut self, p: IVec2) {
+        if self.changes.len() >= self.area().max(64) {
+            self.changed_all();
+        }
+        self.changes.push(p);
+    }
+
+    fn changed_all(&mut self) {
+        self.changes_start = self.generation() + 1;
+        self.changes.clear();
+    }
+
     pub fn print_grid(&self) {
         for y in (0..self.height()).rev() {
             for x in 0..self.width() {
@@ -148,7 +522,7 @@ impl PathMap2d {
     }
 }
 
-impl SizedGrid for PathMap2d {
+impl<C> SizedGrid for PathMap2d<C> {
     fn size(&self) -> UVec2 {
         self.obstacles.size()
     }
@@ -171,57 +545,150 @@ pub const DIR_8: &[IVec2] = &[
     UP, DOWN, LEFT, RIGHT, UP_LEFT, UP_RIGHT, DOWN_LEFT, DOWN_RIGHT,
 ];
 
-impl PathMap for PathMap2d {
-    type ExitIterator = IntoIter<IVec2, DEFAULT_MAX_EXITS>;
+impl<C: Cost> PathMap2d<C> {
+    /// The movement cost between two adjacent grid points, ignoring links.
+    fn adjacent_cost(&self, a: IVec2, b: IVec2) -> C {
+        self.adjacency.move_cost(b - a)
+    }
+
+    /// The heuristic distance between two grid points, ignoring links.
+    fn grid_distance(&self, a: IVec2, b: IVec2) -> C {
+        self.adjacency.heuristic(a, b)
+    }
+}
+
+impl<C: Cost> PathMap for PathMap2d<C> {
+    type Cost = C;
+    type ExitIterator = Exits;
     fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
         let p = p.into();
         let mut points = ArrayVec::new();
-        let neighbours = match self.adjacency {
-            Adjacency::Cardinal => DIR_4.iter().copied(),
-            _ => DIR_8.iter().copied(),
-        }
-        .map(|adj| p + adj);
-        for adj in neighbours {
-            if !self.obstacles.contains_point(adj) {
-                continue;
+        // Custom neighbourhoods can have any number of moves, so they're
+        // stored alongside links.
+        let mut links = Vec::new();
+        let open = |adj: IVec2| {
+            self.obstacles.contains_point(adj) && !self.obstacles.get(adj) && self.can_cross(p, adj)
+        };
+        match &self.adjacency {
+            Adjacency::Cardinal => points.extend(DIR_4.iter().map(|d| p + *d).filter(|a| open(*a))),
+            Adjacency::Custom(moves) => {
+                links.extend(moves.iter().map(|(d, _)| p + *d).filter(|a| open(*a)))
             }
+            _ => points.extend(DIR_8.iter().map(|d| p + *d).filter(|a| open(*a))),
+        }
 
-            if !self.obstacles.get(adj) {
-                points.push(adj);
+        for (to, _) in self.links.from(p) {
+            if self.obstacles.contains_point(*to)
+                && !self.obstacles.get(*to)
+                && !points.contains(to)
+                && !links.contains(to)
+            {
+                links.push(*to);
             }
         }
-        points.into_iter()
+
+        Exits {
+            adjacent: points.into_iter(),
+            links: links.into_iter(),
+        }
     }
 
-    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
-        match self.adjacency {
-            Adjacency::Cardinal => 1,
-            Adjacency::Octile {
-                cardinal_cost,
-                diagonal_cost,
-            } => {
-                if is_cardinal(a, b) {
-                    cardinal_cost
-                } else {
-                    diagonal_cost
-                }
+    fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> C {
+        let (a, b) = (a.into(), b.into());
+        if !self.links.is_empty() {
+            if let Some(cost) = self.links.cost(a, b) {
+                return cost;
             }
         }
+        self.adjacent_cost(a, b)
     }
 
-    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
-        match self.adjacency {
-            Adjacency::Cardinal => cardinal_heuristic(a, b),
-            Adjacency::Octile {
-                cardinal_cost,
-                diagonal_cost,
-            } => octile_heuristic(a, b, cardinal_cost, diagonal_cost),
+    fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> C {
+        let (a, b) = (a.into(), b.into());
+        let d = self.grid_distance(a, b);
+        match self
+            .links
+            .distance_bound(a, b, |a, b| self.grid_distance(a, b))
+        {
+            Some(bound) => d.min_cost(bound),
+            None => d,
         }
     }
 
     fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
         self.obstacles.get(p)
     }
+
+    fn exits_with_cost(&self, p: impl Into<IVec2>) -> impl Iterator<Item = (IVec2, C)> {
+        let p = p.into();
+        let links = self.links.from(p);
+        self.exits(p).map(move |next| {
+            let cost = match links.iter().find(|(to, _)| *to == next) {
+                Some((_, cost)) => *cost,
+                None => self.adjacent_cost(p, next),
+            };
+            (next, cost)
+        })
+    }
+}
+
+/// An iterator over the exits of a [PathMap2d].
+pub struct Exits {
+    adjacent: arrayvec::IntoIter<IVec2, DEFAULT_MAX_EXITS>,
+    links: std::vec::IntoIter<IVec2>,
+}
+
+impl Iterator for Exits {
+    type Item = IVec2;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.adjacent.next().or_else(|| self.links.next())
+    }
+}
+
+/// Penalties applied when changing direction along a path, used with
+/// [crate::Pathfinder::astar_turning].
+///
+/// Each penalty corresponds to the angle between the incoming and outgoing
+/// directions of a move.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TurnPenalties<C = i32> {
+    pub turn_45: C,
+    pub turn_90: C,
+    pub turn_135: C,
+    pub turn_180: C,
+}
+
+impl<C: Cost> TurnPenalties<C> {
+    /// The penalty for turning from the `incoming` direction to the `outgoing`
+    /// direction.
+    pub fn cost(&self, incoming: impl Into<IVec2>, outgoing: impl Into<IVec2>) -> C {
+        match turn_steps(incoming, outgoing) {
+            1 => self.turn_45,
+            2 => self.turn_90,
+            3 => self.turn_135,
+            4 => self.turn_180,
+            _ => C::ZERO,
+        }
+    }
+}
+
+/// The number of 45 degree steps between two grid directions, from 0 to 4.
+pub fn turn_steps(a: impl Into<IVec2>, b: impl Into<IVec2>) -> u32 {
+    const OCTANTS: [IVec2; 8] = [
+        RIGHT, UP_RIGHT, UP, UP_LEFT, LEFT, DOWN_LEFT, DOWN, DOWN_RIGHT,
+    ];
+    let octant = |d: IVec2| OCTANTS.iter().position(|o| *o == d.signum());
+    let (Some(a), Some(b)) = (octant(a.into()), octant(b.into())) else {
+        return 0;
+    };
+    let diff = (a as i32 - b as i32).rem_euclid(8) as u32;
+    diff.min(8 - diff)
+}
+
+/// The index of an adjacent direction in [DIR_8].
+fn dir_index(dir: IVec2) -> Option<usize> {
+    DIR_8.iter().position(|d| *d == dir)
 }
 
 /// The [taxicab distance](https://en.wikipedia.org/wiki/Taxicab_geometry)
@@ -258,3 +725,147 @@ pub fn octile_heuristic(
     let d = (a.into() - b.into()).abs();
     (tcmd * (d.x - d.y).abs() + diagonal_cost * (d.x + d.y)) / 2
 }
+
+/// The cost of the shortest path between two points on an open 8 way grid,
+/// for any [Cost] type.
+#[inline]
+pub fn octile_distance<C: Cost>(
+    a: impl Into<IVec2>,
+    b: impl Into<IVec2>,
+    cardinal_cost: C,
+    diagonal_cost: C,
+) -> C {
+    let d = (a.into() - b.into()).abs();
+    let (max, min) = (d.max_element(), d.min_element());
+    cardinal_cost * C::from_i32(max - min) + diagonal_cost * C::from_i32(min)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::IVec2;
+
+    use super::*;
+    use crate::Pathfinder;
+
+    #[test]
+    fn links() {
+        let mut map = PathMap2d::new([30, 30]);
+        for y in 0..30 {
+            map.add_obstacle([15, y]);
+        }
+        let mut pf = Pathfinder::new();
+        assert!(pf.astar(&map, [0, 0], [29, 29]).is_none());
+
+        map.add_link([10, 5], [20, 25], 4);
+        map.add_link([25, 2], [2, 28], 4);
+        let path = pf.astar(&map, [0, 0], [29, 29]).unwrap();
+        assert!(path.contains(&IVec2::new(20, 25)));
+
+        // One way only
+        assert!(pf.astar(&map, [20, 25], [10, 5]).is_some());
+        assert!(pf
+            .astar(&map, [29, 29], [0, 0])
+            .unwrap()
+            .contains(&IVec2::new(2, 28)));
+
+        assert!(map.remove_link([25, 2], [2, 28]));
+        assert!(pf.astar(&map, [29, 29], [0, 0]).is_none());
+    }
+
+    #[test]
+    fn link_heuristic_is_admissible() {
+        let mut map = PathMap2d::new([20, 20]);
+        map.add_link([1, 1], [18, 18], 1);
+        map.add_link([18, 0], [0, 18], 2);
+        map.add_link([0, 18], [10, 10], 1);
+        let mut astar = Pathfinder::new();
+        let mut dijkstra = Pathfinder::new();
+        for start in [[0, 0], [5, 5], [19, 0], [10, 19]] {
+            for goal in [[19, 19], [0, 19], [11, 11], [3, 1]] {
+                let expected = dijkstra_cost(&mut dijkstra, &map, start, goal);
+                astar.astar(&map, start, goal).unwrap();
+                assert_eq!(expected, astar.costs()[&IVec2::from(goal)]);
+            }
+        }
+    }
+
+    #[test]
+    fn thin_walls() {
+        let mut map = PathMap2d::new([10, 10]);
+        // A vertical thin wall between x = 4 and x = 5 with a gap at the top
+        for y in 0..9 {
+            map.add_wall([4, y], [5, y]);
+        }
+        let mut pf = Pathfinder::new();
+        let path = pf.astar(&map, [4, 0], [5, 0]).unwrap();
+        assert!(path.contains(&IVec2::new(4, 9)) || path.contains(&IVec2::new(4, 8)));
+        for w in path.windows(2) {
+            let crossed = (w[0].x <= 4) != (w[1].x <= 4);
+            assert!(!crossed || w[0].y.max(w[1].y) == 9, "{} -> {}", w[0], w[1]);
+        }
+
+        map.add_wall([4, 9], [5, 9]);
+        assert!(pf.astar(&map, [4, 0], [5, 0]).is_none());
+
+        map.remove_wall([4, 3], [5, 3]);
+        assert_eq!(2, pf.astar(&map, [4, 3], [5, 3]).unwrap().len());
+        map.clear_blocked_edges();
+        assert!(!map.is_edge_blocked([4, 0], [5, 0]));
+    }
+
+    #[test]
+    fn one_way() {
+        let mut map = PathMap2d::new([5, 1]);
+        map.adjacency = Adjacency::Cardinal;
+        map.set_one_way_tile([2, 0], RIGHT);
+        let mut pf = Pathfinder::new();
+        assert!(pf.astar(&map, [0, 0], [4, 0]).is_some());
+        assert!(pf.astar(&map, [4, 0], [0, 0]).is_none());
+        assert!(map.exits([2, 0]).eq([IVec2::new(3, 0)]));
+    }
+
+    #[test]
+    fn adjacency_presets() {
+        let mut pf = Pathfinder::new();
+
+        let mut map = PathMap2d::new([10, 10]);
+        map.adjacency = Adjacency::Chebyshev { cost: 1 };
+        pf.astar(&map, [0, 0], [5, 3]).unwrap();
+        assert_eq!(5, pf.costs()[&IVec2::new(5, 3)]);
+        assert_eq!(5, map.distance([0, 0], [5, 3]));
+
+        map.adjacency = Adjacency::Euclidean { cost: 10 };
+        pf.astar(&map, [0, 0], [5, 3]).unwrap();
+        assert_eq!(3 * 14 + 2 * 10, pf.costs()[&IVec2::new(5, 3)]);
+        assert_eq!(3 * 14 + 2 * 10, map.distance([0, 0], [5, 3]));
+
+        map.adjacency = Adjacency::knight(1);
+        assert_eq!(2, map.exits([0, 0]).count());
+        let path = pf.astar(&map, [0, 0], [1, 1]).unwrap();
+        assert_eq!(5, path.len());
+        assert!(path
+            .windows(2)
+            .all(|w| (w[1] - w[0]).abs().element_sum() == 3));
+        assert!(map.distance([0, 0], [1, 1]) <= 4);
+    }
+
+    #[test]
+    fn exits_with_cost() {
+        let mut map = PathMap2d::new([10, 10]);
+        map.add_link([5, 5], [5, 6], 7);
+        map.add_link([5, 5], [9, 9], 1);
+        map.add_obstacle([4, 4]);
+        for p in [[5, 5], [0, 0], [3, 3]] {
+            let expected: Vec<_> = map.exits(p).map(|n| (n, map.cost(p, n))).collect();
+            assert!(map.exits_with_cost(p).eq(expected));
+        }
+        assert!(map
+            .exits_with_cost([5, 5])
+            .any(|e| e == (IVec2::new(5, 6), 7)));
+    }
+
+    fn dijkstra_cost(pf: &mut Pathfinder, map: &PathMap2d, start: [i32; 2], goal: [i32; 2]) -> i32 {
+        pf.dijkstra(map, Some(start), goal);
+        pf.costs()[&IVec2::from(goal)]
+    }
+}
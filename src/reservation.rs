@@ -0,0 +1,153 @@
+//! A table of reserved positions in space and time for planning the paths of
+//! multiple agents so they don't collide.
+
+use ahash::{HashMap, HashSet};
+use glam::IVec2;
+
+/// A table of positions that are reserved at specific timesteps.
+///
+/// Used with [crate::Pathfinder::astar_reserved] to plan paths for multiple agents
+/// one after another. Once an agent's path is planned it should be added to the
+/// table via [ReservationTable::reserve_path] so that agents planned afterwards
+/// will avoid it.
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::*;
+/// let map = PathMap2d::new([10, 10]);
+/// let mut pf = Pathfinder::new();
+/// let mut reservations = ReservationTable::new();
+///
+/// let a = pf.astar_reserved(&map, [0, 0], [5, 0], 0, 20, &reservations).unwrap();
+/// reservations.reserve_path(a, 0);
+/// // The second agent will have to step around the first
+/// let b = pf.astar_reserved(&map, [5, 0], [0, 0], 0, 20, &reservations).unwrap();
+/// assert!(b.iter().any(|p| p.y != 0));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ReservationTable {
+    cells: HashSet<(IVec2, u32)>,
+    moves: HashSet<(IVec2, IVec2, u32)>,
+    /// The last time each position is reserved, used to check if an agent
+    /// can remain at a position indefinitely.
+    last_reserved: HashMap<IVec2, u32>,
+    /// Positions that are reserved from a given time onwards.
+    parked: HashMap<IVec2, u32>,
+}
+
+impl ReservationTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve a position at a single timestep.
+    pub fn reserve(&mut self, p: impl Into<IVec2>, t: u32) {
+        let p = p.into();
+        self.cells.insert((p, t));
+        let last = self.last_reserved.entry(p).or_insert(t);
+        *last = (*last).max(t);
+    }
+
+    /// Reserve a move from one position to another, leaving at timestep `t` and
+    /// arriving at `t + 1`. This prevents other agents from swapping places
+    /// along the same edge.
+    pub fn reserve_move(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>, t: u32) {
+        self.moves.insert((from.into(), to.into(), t));
+    }
+
+    /// Reserve a position from timestep `t` onwards.
+    pub fn reserve_from(&mut self, p: impl Into<IVec2>, t: u32) {
+        let p = p.into();
+        let start = self.parked.entry(p).or_insert(t);
+        *start = (*start).min(t);
+    }
+
+    /// Reserve every position and move along a path, where the first point in
+    /// the path is at timestep `start_time`. The final position of the path is
+    /// reserved indefinitely.
+    pub fn reserve_path(&mut self, path: &[IVec2], start_time: u32) {
+        for (i, p) in path.iter().enumerate() {
+            let t = start_time + i as u32;
+            self.reserve(*p, t);
+            if let Some(next) = path.get(i + 1) {
+                if next != p {
+                    self.reserve_move(*p, *next, t);
+                }
+            }
+        }
+        if let Some(last) = path.last() {
+            self.reserve_from(*last, start_time + path.len() as u32 - 1);
+        }
+    }
+
+    /// Whether a position is reserved at the given timestep.
+    pub fn is_reserved(&self, p: impl Into<IVec2>, t: u32) -> bool {
+        let p = p.into();
+        self.cells.contains(&(p, t)) || self.parked.get(&p).is_some_and(|start| *start <= t)
+    }
+
+    /// Whether a position is reserved at any timestep from `t` onwards.
+    pub fn is_reserved_after(&self, p: impl Into<IVec2>, t: u32) -> bool {
+        let p = p.into();
+        self.last_reserved.get(&p).is_some_and(|last| *last >= t) || self.parked.contains_key(&p)
+    }
+
+    /// Whether moving from one position to another, leaving at timestep `t`,
+    /// would collide with a reservation. This includes the destination being
+    /// reserved at `t + 1` or another agent moving along the same edge in the
+    /// opposite direction.
+    pub fn is_move_blocked(&self, from: impl Into<IVec2>, to: impl Into<IVec2>, t: u32) -> bool {
+        let (from, to) = (from.into(), to.into());
+        self.is_reserved(to, t + 1) || self.moves.contains(&(to, from, t))
+    }
+
+    /// Remove all reservations.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.moves.clear();
+        self.last_reserved.clear();
+        self.parked.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pathmap::Adjacency, PathMap2d, Pathfinder};
+
+    #[test]
+    fn swap_conflict() {
+        let mut table = ReservationTable::new();
+        table.reserve_path(&[IVec2::new(0, 0), IVec2::new(1, 0)], 0);
+
+        // Moving against the reserved move along the same edge is blocked
+        assert!(table.is_move_blocked([1, 0], [0, 0], 0));
+        assert!(!table.is_move_blocked([1, 0], [0, 0], 1));
+        // Following behind is allowed, but not moving into an occupied position
+        assert!(!table.is_move_blocked([0, 1], [0, 0], 0));
+        assert!(table.is_move_blocked([1, 1], [1, 0], 0));
+    }
+
+    #[test]
+    fn parked() {
+        let mut table = ReservationTable::new();
+        table.reserve_path(&[IVec2::new(0, 0), IVec2::new(1, 0), IVec2::new(2, 0)], 0);
+
+        assert!(!table.is_reserved([2, 0], 1));
+        assert!(table.is_reserved([2, 0], 2));
+        assert!(table.is_reserved([2, 0], 1000));
+        assert!(table.is_reserved_after([2, 0], 1000));
+        assert!(!table.is_reserved_after([0, 0], 1));
+
+        // Another agent can't end its path on a parked agent, but can pass
+        // through a position once it's free
+        let mut map = PathMap2d::new([5, 3]);
+        map.adjacency = Adjacency::Cardinal;
+        let mut pf = Pathfinder::new();
+        let path = pf
+            .astar_reserved(&map, [4, 0], [0, 0], 0, 20, &table)
+            .unwrap();
+        assert_eq!([0, 0], path.last().unwrap().to_array());
+        assert!(!path.contains(&IVec2::new(2, 0)));
+    }
+}
@@ -0,0 +1,182 @@
+//! A generic A* search over arbitrary states.
+//!
+//! Used for searches where a position alone isn't enough to describe a node,
+//! such as a position at a point in time.
+
+use ahash::{HashMap, HashMapExt};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::hash::Hash;
+
+use crate::cost::Cost;
+
+/// A reusable [A*] search over arbitrary states.
+///
+/// Maintains internal state so it can be re-used to avoid allocations.
+///
+/// [A*]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#astar
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::state_search::StateSearch;
+/// // Count up to 10 by adding 1 or 3 at a time
+/// let mut search = StateSearch::new();
+/// let path = search
+///     .astar(0, |s| s == 10, |s| [(s + 1, 1), (s + 3, 1)], |s| (10 - s).max(0) / 3)
+///     .unwrap();
+/// assert_eq!(5, path.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct StateSearch<S, C = i32> {
+    frontier: BinaryHeap<StateCell<S, C>>,
+    came_from: HashMap<S, S>,
+    costs: HashMap<S, C>,
+    path: Vec<S>,
+    counter: u32,
+}
+
+impl<S, C> Default for StateSearch<S, C> {
+    fn default() -> Self {
+        Self {
+            frontier: BinaryHeap::new(),
+            came_from: HashMap::new(),
+            costs: HashMap::new(),
+            path: Vec::new(),
+            counter: 0,
+        }
+    }
+}
+
+impl<S: Copy + Eq + Hash, C: Cost> StateSearch<S, C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Find the cheapest path from `start` to any state that satisfies `is_goal`.
+    ///
+    /// `successors` should return every state that can be reached from the
+    /// given state along with the cost of moving to it. `heuristic` should
+    /// estimate the remaining cost to a goal and must never overestimate it.
+    ///
+    /// Returns a slice of states representing the path, or [None] if no goal
+    /// can be reached.
+    pub fn astar<I>(
+        &mut self,
+        start: S,
+        mut is_goal: impl FnMut(S) -> bool,
+        mut successors: impl FnMut(S) -> I,
+        heuristic: impl Fn(S) -> C,
+    ) -> Option<&[S]>
+    where
+        I: IntoIterator<Item = (S, C)>,
+    {
+        self.clear();
+        self.push(start, C::ZERO);
+        self.costs.insert(start, C::ZERO);
+
+        let mut goal = None;
+        while let Some(cell) = self.frontier.pop() {
+            let curr = cell.state;
+            if is_goal(curr) {
+                goal = Some(curr);
+                break;
+            }
+
+            let curr_cost = self.costs[&curr];
+            for (next, cost) in successors(curr) {
+                let new_cost = curr_cost + cost;
+                if self
+                    .costs
+                    .get(&next)
+                    .is_none_or(|c| new_cost.total_cmp(c).is_lt())
+                {
+                    self.costs.insert(next, new_cost);
+                    self.push(next, new_cost + heuristic(next));
+                    self.came_from.insert(next, curr);
+                }
+            }
+        }
+
+        self.build_path(start, goal?)
+    }
+
+    /// Construct a path from start to goal from the most recent search.
+    pub fn build_path(&mut self, start: S, goal: S) -> Option<&[S]> {
+        self.path.clear();
+        let mut curr = goal;
+        self.path.push(curr);
+        while curr != start {
+            curr = *self.came_from.get(&curr)?;
+            self.path.push(curr);
+        }
+        self.path.reverse();
+        Some(self.path.as_slice())
+    }
+
+    /// Clear all internal data.
+    pub fn clear(&mut self) {
+        self.frontier.clear();
+        self.came_from.clear();
+        self.costs.clear();
+        self.path.clear();
+        self.counter = 0;
+    }
+
+    /// Retrieve a reference to the `came_from` map which is populated during
+    /// the search.
+    pub fn came_from(&self) -> &HashMap<S, S> {
+        &self.came_from
+    }
+
+    /// Retrieve a reference to the `costs` map which is populated during
+    /// the search.
+    pub fn costs(&self) -> &HashMap<S, C> {
+        &self.costs
+    }
+
+    /// Retrieve a slice of the most recently built path.
+    pub fn path(&self) -> &[S] {
+        &self.path
+    }
+
+    fn push(&mut self, state: S, cost: C) {
+        self.frontier.push(StateCell {
+            cost,
+            order: self.counter,
+            state,
+        });
+        self.counter += 1;
+    }
+}
+
+/// A cell for the search frontier.
+#[derive(Debug, Clone, Copy)]
+struct StateCell<S, C> {
+    cost: C,
+    order: u32,
+    state: S,
+}
+
+impl<S, C: Cost> PartialEq for StateCell<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S, C: Cost> Eq for StateCell<S, C> {}
+
+impl<S, C: Cost> Ord for StateCell<S, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // order by cost, then by the order states were pushed
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+impl<S, C: Cost> PartialOrd for StateCell<S, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
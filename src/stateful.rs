@@ -0,0 +1,332 @@
+//! Pathfinding where movement depends on some extra state carried along the
+//! path, such as keys held or switches flipped.
+
+use std::hash::Hash;
+
+use glam::IVec2;
+
+use crate::{cost::Cost, pathmap::PathMap, state_search::StateSearch};
+
+/// A [PathMap] where whether an exit can be taken depends on some extra state,
+/// and taking it may change that state.
+///
+/// The state should be small and cheap to copy and hash, such as a [KeySet].
+pub trait StatefulPathMap: PathMap {
+    type State: Copy + Eq + Hash;
+
+    /// Called for every exit from `from` to `to` while carrying `state`. Returns
+    /// the state after the move, or [None] if the move isn't allowed.
+    fn transition(&self, from: IVec2, to: IVec2, state: Self::State) -> Option<Self::State>;
+}
+
+/// A small set of up to 32 flags, such as keys held or switches flipped.
+///
+/// Flags are numbered from 0 to 31. Using a higher flag panics.
+///
+/// # Example
+/// ```
+/// use sark_pathfinding::stateful::KeySet;
+/// let keys = KeySet::new().with(2);
+/// assert!(keys.contains(2));
+/// assert!(!keys.contains(0));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeySet(pub u32);
+
+impl KeySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of the set with the given flag added.
+    pub fn with(self, key: u32) -> Self {
+        Self(self.0 | Self::bit(key))
+    }
+
+    /// A copy of the set with the given flag removed.
+    pub fn without(self, key: u32) -> Self {
+        Self(self.0 & !Self::bit(key))
+    }
+
+    pub fn insert(&mut self, key: u32) {
+        *self = self.with(key);
+    }
+
+    pub fn remove(&mut self, key: u32) {
+        *self = self.without(key);
+    }
+
+    pub fn contains(&self, key: u32) -> bool {
+        self.0 & Self::bit(key) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    fn bit(key: u32) -> u32 {
+        1u32.checked_shl(key)
+            .unwrap_or_else(|| panic!("KeySet flag {key} is out of range, flags must be below 32"))
+    }
+}
+
+/// A reusable [A*] search over a [StatefulPathMap], finding paths that may need
+/// to visit positions in a certain order, like fetching a key before opening a door.
+///
+/// Maintains internal state so it can be re-used to avoid allocations.
+///
+/// [A*]: https://www.redblobgames.com/pathfinding/a-star/introduction.html#astar
+///
+/// # Example
+/// ```
+/// use glam::IVec2;
+/// use sark_pathfinding::{stateful::*, *};
+///
+/// struct Dungeon(PathMap2d);
+///
+/// impl PathMap for Dungeon {
+///     type Cost = i32;
+///     type ExitIterator = <PathMap2d as PathMap>::ExitIterator;
+///     fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator { self.0.exits(p) }
+///     fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 { self.0.cost(a, b) }
+///     fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 { self.0.distance(a, b) }
+///     fn is_obstacle(&self, p: impl Into<IVec2>) -> bool { self.0.is_obstacle(p) }
+/// }
+///
+/// impl StatefulPathMap for Dungeon {
+///     type State = KeySet;
+///     fn transition(&self, _: IVec2, to: IVec2, keys: KeySet) -> Option<KeySet> {
+///         match to.to_array() {
+///             // The key
+///             [0, 4] => Some(keys.with(0)),
+///             // The door
+///             [5, 0] if !keys.contains(0) => None,
+///             _ => Some(keys),
+///         }
+///     }
+/// }
+///
+/// let mut map = PathMap2d::new([10, 5]);
+/// for y in 1..5 {
+///     map.add_obstacle([5, y]);
+/// }
+/// let map = Dungeon(map);
+/// let mut pf = StatefulPathfinder::new();
+/// let path = pf.astar(&map, [0, 0], KeySet::new(), [9, 0]).unwrap();
+/// assert!(path.iter().any(|(p, _)| *p == IVec2::new(0, 4)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatefulPathfinder<S, C = i32> {
+    search: StateSearch<(IVec2, S), C>,
+}
+
+impl<S, C> Default for StatefulPathfinder<S, C> {
+    fn default() -> Self {
+        Self {
+            search: StateSearch::default(),
+        }
+    }
+}
+
+impl<S: Copy + Eq + Hash, C: Cost> StatefulPathfinder<S, C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Find the cheapest path from `start`, carrying the given initial state, to
+    /// `goal` with any state.
+    ///
+    /// Returns a slice of positions and the state held on arriving at each, or
+    /// [None] if no path could be found.
+    pub fn astar<M: StatefulPathMap<State = S, Cost = C>>(
+        &mut self,
+        map: &M,
+        start: impl Into<IVec2>,
+        state: S,
+        goal: impl Into<IVec2>,
+    ) -> Option<&[(IVec2, S)]> {
+        let goal = goal.into();
+        self.astar_fn(
+            map,
+            start,
+            state,
+            |p, _| p == goal,
+            |p| map.distance(goal, p),
+        )
+    }
+
+    /// Find the cheapest path from `start`, carrying the given initial state, to
+    /// any position and state that satisfies `is_goal`.
+    ///
+    /// The start position's transition is applied to the initial state first, as
+    /// `transition(start, start, state)`, so anything lying on the start position
+    /// is picked up. Returns [None] if that transition isn't allowed.
+    ///
+    /// `heuristic` should estimate the remaining cost from a position to a goal
+    /// and must never overestimate it.
+    pub fn astar_fn<M: StatefulPathMap<State = S, Cost = C>>(
+        &mut self,
+        map: &M,
+        start: impl Into<IVec2>,
+        state: S,
+        is_goal: impl Fn(IVec2, S) -> bool,
+        heuristic: impl Fn(IVec2) -> C,
+    ) -> Option<&[(IVec2, S)]> {
+        let start = start.into();
+        let Some(state) = map.transition(start, start, state) else {
+            self.search.clear();
+            return None;
+        };
+        self.search.astar(
+            (start, state),
+            |(p, s)| is_goal(p, s),
+            |(p, s)| {
+                map.exits_with_cost(p).filter_map(move |(next, cost)| {
+                    let s = map.transition(p, next, s)?;
+                    Some(((next, s), cost))
+                })
+            },
+            |(p, _)| heuristic(p),
+        )
+    }
+
+    /// The most recently calculated path.
+    pub fn path(&self) -> &[(IVec2, S)] {
+        self.search.path()
+    }
+
+    pub fn clear(&mut self) {
+        self.search.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathMap2d;
+
+    /// Keys are at `keys[i]` and the door for key `i` is at `doors[i]`.
+    struct Dungeon {
+        map: PathMap2d,
+        keys: Vec<IVec2>,
+        doors: Vec<IVec2>,
+    }
+
+    impl PathMap for Dungeon {
+        type Cost = i32;
+        type ExitIterator = <PathMap2d as PathMap>::ExitIterator;
+
+        fn exits(&self, p: impl Into<IVec2>) -> Self::ExitIterator {
+            self.map.exits(p)
+        }
+
+        fn cost(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
+            self.map.cost(a, b)
+        }
+
+        fn distance(&self, a: impl Into<IVec2>, b: impl Into<IVec2>) -> i32 {
+            self.map.distance(a, b)
+        }
+
+        fn is_obstacle(&self, p: impl Into<IVec2>) -> bool {
+            self.map.is_obstacle(p)
+        }
+    }
+
+    impl StatefulPathMap for Dungeon {
+        type State = KeySet;
+
+        fn transition(&self, _: IVec2, to: IVec2, keys: KeySet) -> Option<KeySet> {
+            if let Some(i) = self.doors.iter().position(|d| *d == to) {
+                if !keys.contains(i as u32) {
+                    return None;
+                }
+            }
+            match self.keys.iter().position(|k| *k == to) {
+                Some(i) => Some(keys.with(i as u32)),
+                None => Some(keys),
+            }
+        }
+    }
+
+    #[test]
+    fn keys_and_doors() {
+        // The two halves are only connected at (4, 1), where the door is
+        let map = PathMap2d::from_string(
+            "
+#########
+#...#...#
+#.#.#.#.#
+#.#.....#
+#########
+",
+            '#',
+        )
+        .unwrap();
+        let mut dungeon = Dungeon {
+            map,
+            keys: vec![IVec2::new(7, 3)],
+            doors: vec![IVec2::new(4, 1)],
+        };
+        let mut pf = StatefulPathfinder::new();
+        // The key is behind the door
+        assert!(pf.astar(&dungeon, [1, 1], KeySet::new(), [7, 1]).is_none());
+
+        dungeon.keys = vec![IVec2::new(1, 3)];
+        let path = pf
+            .astar(&dungeon, [3, 3], KeySet::new(), [7, 1])
+            .unwrap()
+            .to_vec();
+        let key = path.iter().position(|(p, _)| *p == IVec2::new(1, 3));
+        let door = path.iter().position(|(p, _)| *p == IVec2::new(4, 1));
+        assert!(key.unwrap() < door.unwrap());
+        assert!(path.last().unwrap().1.contains(0));
+
+        // Already holding the key goes straight through
+        let direct = pf
+            .astar(&dungeon, [3, 3], KeySet::new().with(0), [7, 1])
+            .unwrap();
+        assert!(direct.len() < path.len());
+    }
+
+    #[test]
+    fn key_set() {
+        let mut keys = KeySet::new();
+        assert!(keys.is_empty());
+        keys.insert(3);
+        keys.insert(31);
+        assert!(keys.contains(3) && keys.contains(31));
+        keys.remove(3);
+        assert!(!keys.contains(3));
+        assert_eq!(KeySet::new().with(31), keys);
+    }
+
+    #[test]
+    #[should_panic]
+    fn key_set_out_of_range() {
+        KeySet::new().with(32);
+    }
+
+    #[test]
+    fn key_on_start() {
+        let map = PathMap2d::from_string(
+            "
+#######
+#..#..#
+#######
+",
+            '#',
+        )
+        .unwrap();
+        let mut dungeon = Dungeon {
+            map,
+            keys: vec![IVec2::new(1, 1)],
+            doors: vec![IVec2::new(3, 1)],
+        };
+        dungeon.map.remove_obstacle([3, 1]);
+        let mut pf = StatefulPathfinder::new();
+        let path = pf.astar(&dungeon, [1, 1], KeySet::new(), [5, 1]).unwrap();
+        assert!(path[0].1.contains(0));
+        assert_eq!([5, 1], path.last().unwrap().0.to_array());
+    }
+}